@@ -0,0 +1,28 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rustc-check-cfg=cfg(todo2_span_file)");
+    // A proc macro is always built by the same compiler that runs it,
+    // so the version that we get here is the version of the compiler that expands the `rustc` condition
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let Ok(output) = Command::new(rustc).arg("-V").output() else {
+        return;
+    };
+    let Ok(version) = String::from_utf8(output.stdout) else {
+        return;
+    };
+    println!("cargo:rustc-env=TODO2_RUSTC_VERSION={}", version.trim());
+
+    // `proc_macro::Span::file` is stable since 1.88
+    let minor = version
+        .split_whitespace()
+        .nth(1)
+        .and_then(|v| v.split('.').nth(1))
+        .and_then(|minor| minor.parse::<u32>().ok());
+    if matches!(minor, Some(minor) if minor >= 88) {
+        println!("cargo:rustc-cfg=todo2_span_file");
+    }
+}
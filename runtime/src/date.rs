@@ -0,0 +1,258 @@
+//! The calendar math and the date string parser, shared by the `todo2` macro and [`parse_now`](crate::parse_now)
+
+/// The seconds in a day, there are no leap seconds in the unix time
+pub const ONE_DAY: i64 = 86_400;
+
+pub fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Returns the days of the month, it must be between 1 and 12
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The days since 1970-01-01 in the proleptic gregorian calendar, negative before it.
+///
+/// The years start on March 1st, so the leap day is the last day of the year,
+/// and they're grouped in eras of 400 years that repeat the same calendar, see <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, the month and the day of the days since 1970-01-01, the inverse of [`days_from_civil`]
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month as u32, day as u32)
+}
+
+/// A date string in the ISO 8601 extended format that RFC 3339 uses, e.g. `2025-03-26` or `2025-03-26T09:00:00+02:00`.
+///
+/// It's `YYYY-MM-DD`, `YYYY-MM` or `YYYY`, optionally followed by `T` or a space and the time `HH:MM` or `HH:MM:SS`,
+/// then `Z` or an offset like `+02:00`, `+0200` or `+02`. The fractions of a second are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rfc3339 {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+    /// The seconds since the start of the day
+    pub secs: u32,
+    /// The offset in seconds east of UTC
+    pub offset: i32,
+}
+
+impl Rfc3339 {
+    /// Parses the string, the time and the offset are checked, but the month and the day are left to the caller,
+    /// so it can report them against the calendar
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (date, time) = match s.split_once(['T', 't', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+        let mut parts = date.split('-');
+        let year = digits(parts.next(), 4, "year", s)? as i32;
+        let month = parts
+            .next()
+            .map(|month| digits(Some(month), 2, "month", s))
+            .transpose()?;
+        let day = parts
+            .next()
+            .map(|day| digits(Some(day), 2, "day", s))
+            .transpose()?;
+        if parts.next().is_some() || (time.is_some() && day.is_none()) {
+            return Err(format!(
+                "Invalid date `{}`, expected `YYYY-MM-DD` with an optional time like `YYYY-MM-DDTHH:MM:SSZ`",
+                s
+            ));
+        }
+        let mut date = Self {
+            year,
+            month,
+            day,
+            secs: 0,
+            offset: 0,
+        };
+        let Some(time) = time else {
+            return Ok(date);
+        };
+        let (time, zone) = match time.find(['Z', 'z', '+', '-']) {
+            Some(i) => time.split_at(i),
+            None => (time, ""),
+        };
+        // The fractions of a second don't change the time stamp
+        let time = time.split_once(['.', ',']).map_or(time, |(time, _)| time);
+        let mut parts = time.split(':');
+        let hour = digits(parts.next(), 2, "hour", s)?;
+        let minute = digits(parts.next(), 2, "minute", s)?;
+        let second = match parts.next() {
+            Some(second) => digits(Some(second), 2, "second", s)?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return Err(format!(
+                "Invalid time in `{}`, expected `HH:MM` or `HH:MM:SS`",
+                s
+            ));
+        }
+        if hour > 23 {
+            return Err(format!("Invalid hour `{}`, it must be less than 24", hour));
+        }
+        if minute > 59 {
+            return Err(format!(
+                "Invalid minute `{}`, it must be less than 60",
+                minute
+            ));
+        }
+        if second > 59 {
+            return Err(format!(
+                "Invalid second `{}`, it must be less than 60",
+                second
+            ));
+        }
+        date.secs = hour * 3600 + minute * 60 + second;
+        date.offset = match zone {
+            "" | "Z" | "z" => 0,
+            _ => offset(zone, s)?,
+        };
+        Ok(date)
+    }
+
+    /// The unix time stamp, the missing month and day are the first ones
+    pub fn time_stamp(&self) -> i64 {
+        let days = days_from_civil(self.year, self.month.unwrap_or(1), self.day.unwrap_or(1));
+        days * ONE_DAY + i64::from(self.secs) - i64::from(self.offset)
+    }
+}
+
+/// Takes a part of the date string that must be `len` digits, e.g. the month in `2025-03-26`
+fn digits(part: Option<&str>, len: usize, name: &str, s: &str) -> Result<u32, String> {
+    match part {
+        Some(part) if part.len() == len && part.bytes().all(|b| b.is_ascii_digit()) => {
+            Ok(part.parse().unwrap())
+        }
+        _ => Err(format!(
+            "Invalid {} in `{}`, expected {} digits",
+            name, s, len
+        )),
+    }
+}
+
+/// Parses the offset at the end of the date string, `+HH:MM`, `+HHMM` or `+HH` and the same with `-`
+fn offset(zone: &str, s: &str) -> Result<i32, String> {
+    let invalid = || {
+        format!(
+            "Invalid offset `{}` in `{}`, expected `Z`, `+HH:MM` or `-HH:MM`",
+            zone, s
+        )
+    };
+    // The sign is ASCII, so the rest starts at a char boundary, but the rest may not be ASCII
+    let (sign, rest) = zone.split_at(1);
+    let (hours, minutes) = match (rest.len(), rest.get(..2), rest.get(2..)) {
+        (2, Some(hours), _) => (hours, "00"),
+        (4, Some(hours), Some(minutes)) => (hours, minutes),
+        (5, Some(hours), Some(minutes)) => (hours, minutes.strip_prefix(':').ok_or_else(invalid)?),
+        _ => return Err(invalid()),
+    };
+    let hours = digits(Some(hours), 2, "offset hours", s)?;
+    let minutes = digits(Some(minutes), 2, "offset minutes", s)?;
+    if hours > 23 {
+        return Err(format!(
+            "Invalid offset hours `{}`, it must be less than 24",
+            hours
+        ));
+    }
+    if minutes > 59 {
+        return Err(format!(
+            "Invalid offset minutes `{}`, it must be less than 60",
+            minutes
+        ));
+    }
+    let offset = (hours * 3600 + minutes * 60) as i32;
+    Ok(if sign == "-" { -offset } else { offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert!((1..=days_in_month(year, month)).contains(&day));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_parse_rfc3339() -> Result<(), String> {
+        assert_eq!(
+            Rfc3339::parse("2025-03-26T09:00:30.5+02:00")?,
+            Rfc3339 {
+                year: 2025,
+                month: Some(3),
+                day: Some(26),
+                secs: 9 * 3600 + 30,
+                offset: 2 * 3600,
+            }
+        );
+        let date = Rfc3339::parse("2025-03")?;
+        assert_eq!((date.month, date.day, date.secs), (Some(3), None, 0));
+        assert_eq!(Rfc3339::parse("2025-03-26 09:00-0530")?.offset, -19_800);
+        assert_eq!(Rfc3339::parse("2025-03-26t09:00-05")?.offset, -18_000);
+        assert_eq!(
+            Rfc3339::parse("2026-01-01T00:00Z")?.time_stamp(),
+            1_767_225_600
+        );
+        // the calendar is left to the caller
+        assert_eq!(Rfc3339::parse("2025-02-31")?.day, Some(31));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_rfc3339() {
+        assert_eq!(
+            Rfc3339::parse("2025-03-26T09:00+0é00").unwrap_err(),
+            "Invalid offset `+0é00` in `2025-03-26T09:00+0é00`, expected `Z`, `+HH:MM` or `-HH:MM`"
+        );
+        assert_eq!(
+            Rfc3339::parse("2025-03-26T09:00+02é").unwrap_err(),
+            "Invalid offset minutes in `2025-03-26T09:00+02é`, expected 2 digits"
+        );
+        assert!(Rfc3339::parse("2025-03-26T09:00+0é0").is_err());
+        assert!(Rfc3339::parse("2025-03-26T09:00+é").is_err());
+        assert!(Rfc3339::parse("2025-03-26T09:00+02:0").is_err());
+        assert!(Rfc3339::parse("2025-03-26T09:00+02;00").is_err());
+        assert!(Rfc3339::parse("2025-03-26T09:00+24:00").is_err());
+        assert!(Rfc3339::parse("2025-03-26T09:00+02:60").is_err());
+        assert!(Rfc3339::parse("202é-03-26").is_err());
+        assert!(Rfc3339::parse("").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! # todo2-runtime
+//! The runtime support of [todo2](https://docs.rs/todo2), the code that the `todo!` expansions call instead of repeating it at every call site.
+//!
+//! you only need it with the `test-clock` feature of `todo2`, add it to your dependencies next to `todo2`,
+//! nothing of it runs unless the feature is enabled:
+//! ```toml
+//! [dependencies]
+//! todo2 = "0.1.0"
+//! todo2-runtime = "0.1.0"
+//!
+//! [dev-dependencies]
+//! todo2 = { version = "0.1.0", features = ["test-clock"] }
+//! ```
+//! the `todo2` macro also uses it to parse the time stamps, so the build and the runtime accept the same ones.
+pub mod date;
+
+use crate::date::{days_in_month, Rfc3339};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+
+/// The variable that overrides the runtime clock, for the `test-clock` feature
+pub const NOW_ENV: &str = "TODO2_NOW";
+
+/// Returns the time stamp in `TODO2_NOW`, it's read the first time that any `todo!` checks the clock in the process.
+///
+/// # Panics
+/// If the variable isn't a valid time stamp, see [`parse_now`]
+pub fn now_override() -> Option<u64> {
+    static ONCE: Once = Once::new();
+    // `u64::MAX` when it isn't overridden
+    static NOW: AtomicU64 = AtomicU64::new(u64::MAX);
+    ONCE.call_once(|| {
+        if let Ok(now) = std::env::var(NOW_ENV) {
+            let now = parse_now(&now).unwrap_or_else(|| {
+                panic!(
+                    "Invalid `{}` `{}`, expected a unix time stamp or a date like `2026-01-01T00:00:00Z`",
+                    NOW_ENV, now
+                )
+            });
+            NOW.store(now, Ordering::Relaxed);
+        }
+    });
+    match NOW.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        now => Some(now),
+    }
+}
+
+/// Parses a unix time stamp, or an RFC 3339 date with an optional time and offset, e.g. `2026-01-01T09:00:00+02:00`
+pub fn parse_now(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse().ok();
+    }
+    let date = Rfc3339::parse(s).ok()?;
+    let (Some(month), Some(day)) = (date.month, date.day) else {
+        return None;
+    };
+    if !(1..=12).contains(&month) || !(1..=days_in_month(date.year, month)).contains(&day) {
+        return None;
+    }
+    u64::try_from(date.time_stamp()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_now() {
+        assert_eq!(parse_now("1767225600"), Some(1_767_225_600));
+        assert_eq!(parse_now("2026-01-01T00:00:00Z"), Some(1_767_225_600));
+        assert_eq!(parse_now(" 2026-01-01 "), Some(1_767_225_600));
+        assert_eq!(parse_now("2026-01-01T09:30"), Some(1_767_259_800));
+        assert_eq!(parse_now("2026-01-01 09:30:15z"), Some(1_767_259_815));
+        assert_eq!(parse_now("2026-01-01T09:30:00+02:00"), Some(1_767_252_600));
+        assert_eq!(parse_now("2026-01-01T00:00:00-05:30"), Some(1_767_245_400));
+        assert_eq!(parse_now("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(parse_now("1970-01-01"), Some(0));
+        assert_eq!(parse_now("1969-12-31"), None);
+        assert_eq!(parse_now(""), None);
+        assert_eq!(parse_now("2026-13-01"), None);
+        assert_eq!(parse_now("2026-02-29"), None);
+        assert_eq!(parse_now("2026-01"), None);
+        assert_eq!(parse_now("2026-01-01T24:00"), None);
+        assert_eq!(parse_now("2026-01-01T09"), None);
+        assert_eq!(parse_now("2026-01-01T09:30+2"), None);
+        assert_eq!(parse_now("2026/01/01"), None);
+        assert_eq!(parse_now("tomorrow"), None);
+    }
+}
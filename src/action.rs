@@ -0,0 +1,655 @@
+use crate::clock::runtime_now;
+use crate::date::fmt_rfc3339;
+use crate::Result;
+use proc_macro2::token_stream::IntoIter;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use std::iter::Peekable;
+
+/// What the macro does when a condition is met, i.e. the sink of the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Panics at runtime
+    Panic,
+    /// Logs an error at runtime with the `log` crate
+    Log,
+    /// Emits an error event at runtime with the `tracing` crate
+    Tracing,
+    /// Prints the message to the standard error at runtime
+    Eprint,
+    /// Fails the build if the condition is known at compile time, otherwise panics at runtime
+    CompileError,
+    /// Emits a compiler warning, the condition has to be known at compile time
+    Warn,
+    /// Does nothing
+    Ignore,
+}
+
+/// How a condition gets decided, every condition kind is one of these
+pub(crate) enum Check {
+    /// The macro found it met when it was expanded
+    Met,
+    /// The compiler decides it with a `cfg` predicate
+    Cfg(TokenStream),
+    /// The compiler decides it in a const evaluation
+    Const(TokenStream),
+    /// It's checked at runtime, and it may already be known to be met when the macro is expanded, e.g. a passed deadline
+    Runtime {
+        cond: TokenStream,
+        met_at_build: Option<bool>,
+    },
+}
+
+/// The kind of the condition, the `tracing` events have it in their fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    /// A deadline, it's `None` when it's an expression that's only known at runtime
+    By(Option<u64>),
+    If,
+    ConstIf,
+    Version,
+    Rustc,
+    Dep,
+    Env,
+}
+
+/// The optional metadata of a TODO, the structured sinks attach it to the message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Metadata {
+    /// Who's responsible for it, `owner: "..."`
+    pub(crate) owner: Option<String>,
+    /// The issue that tracks it, `ticket: "..."`
+    pub(crate) ticket: Option<String>,
+}
+
+/// What the sinks know about the TODO besides the message, the structured ones attach it
+pub(crate) struct Todo<'a> {
+    /// The message that was passed to the macro
+    pub(crate) message: &'a str,
+    pub(crate) kind: Kind,
+    pub(crate) meta: &'a Metadata,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::By(_) => "by",
+            Kind::If => "if",
+            Kind::ConstIf => "const_if",
+            Kind::Version => "version",
+            Kind::Rustc => "rustc",
+            Kind::Dep => "dep",
+            Kind::Env => "env",
+        }
+    }
+}
+
+impl Action {
+    /// The crate-wide action that the `compile-error`, `tracing`, `log` and `eprint` features choose
+    pub(crate) fn from_features() -> Self {
+        if cfg!(feature = "compile-error") {
+            Action::CompileError
+        } else if cfg!(feature = "tracing") {
+            Action::Tracing
+        } else if cfg!(feature = "log") {
+            Action::Log
+        } else if cfg!(feature = "eprint") {
+            Action::Eprint
+        } else {
+            Action::Panic
+        }
+    }
+
+    /// The code that sends the message to the sink when the condition is met
+    pub(crate) fn emit(self, check: Check, msg: &str, todo: &Todo) -> TokenStream {
+        match (self, check) {
+            (Action::Ignore, _) => quote!(),
+            (Action::CompileError, Check::Met) => quote!(::core::compile_error!(#msg);),
+            (Action::Warn, Check::Met) => warning(msg),
+            // An `if` keeps the code after the macro reachable
+            (_, Check::Met) => self.runtime(quote!(true), msg, todo),
+            (Action::CompileError, Check::Cfg(predicate)) => quote! {
+                #[cfg(#predicate)]
+                ::core::compile_error!(#msg);
+            },
+            (Action::Warn, Check::Cfg(predicate)) => {
+                let warning = deprecated_warning(msg);
+                quote! {
+                    #[cfg(#predicate)]
+                    #warning
+                }
+            }
+            (_, Check::Cfg(predicate)) => self.runtime(quote!(::core::cfg!(#predicate)), msg, todo),
+            (Action::CompileError, Check::Const(cond)) => {
+                // The message is a format string in `assert!`
+                let msg = msg.replace('{', "{{").replace('}', "}}");
+                quote!(
+                    const _: () = ::core::assert!(!(#cond), #msg);
+                )
+            }
+            (Action::Warn, Check::Const(_)) => unknown_at_build(),
+            (_, Check::Const(cond)) => self.runtime(cond, msg, todo),
+            (
+                Action::CompileError | Action::Warn,
+                Check::Runtime {
+                    met_at_build: Some(true),
+                    ..
+                },
+            ) => self.emit(Check::Met, msg, todo),
+            // It isn't met yet when we build, so there's nothing to warn about
+            (Action::Warn, Check::Runtime { met_at_build, .. }) if met_at_build.is_some() => {
+                quote!()
+            }
+            (Action::Warn, Check::Runtime { .. }) => unknown_at_build(),
+            // There's nothing to fail the build with
+            (Action::CompileError, Check::Runtime { cond, .. }) => {
+                Action::Panic.runtime(cond, msg, todo)
+            }
+            (_, Check::Runtime { cond, .. }) => self.runtime(cond, msg, todo),
+        }
+    }
+
+    /// Sends the message to a runtime sink if the condition is true at runtime
+    fn runtime(self, cond: TokenStream, msg: &str, todo: &Todo) -> TokenStream {
+        let sink = match self {
+            Action::Log if cfg!(feature = "log-kv") => log_kv_event(msg, todo),
+            Action::Log => quote!(::log::error!("{}", #msg);),
+            Action::Tracing => tracing_event(quote!(error), msg, todo),
+            Action::Eprint if cfg!(feature = "no-std") => {
+                quote!(::core::compile_error!(#NO_STD_EPRINT);)
+            }
+            Action::Eprint => eprint_once(msg, todo),
+            _ => quote!(::core::panic!("{}", #msg);),
+        };
+        quote! {
+            if #cond {
+                #sink
+            }
+        }
+    }
+}
+
+/// Emits a `tracing` warning event at runtime if the condition is true, e.g. when the deadline is close
+pub(crate) fn tracing_warning(cond: TokenStream, msg: &str, todo: &Todo) -> TokenStream {
+    let event = tracing_event(quote!(warn), msg, todo);
+    quote! {
+        if #cond {
+            #event
+        }
+    }
+}
+
+/// A `tracing` event with the structured fields of the TODO, its parent is the current span
+fn tracing_event(level: TokenStream, msg: &str, todo: &Todo) -> TokenStream {
+    let name = todo.kind.name();
+    let deadline = match todo.kind {
+        Kind::By(Some(deadline)) => {
+            let deadline = fmt_rfc3339(deadline);
+            quote!(todo.deadline = #deadline,)
+        }
+        _ => quote!(),
+    };
+    let owner = todo.meta.owner.iter();
+    let ticket = todo.meta.ticket.iter();
+    quote! {
+        ::tracing::#level!(
+            todo.message = #msg,
+            #deadline
+            todo.kind = #name,
+            todo.file = ::core::file!(),
+            todo.line = ::core::line!(),
+            #(todo.owner = #owner,)*
+            #(todo.ticket = #ticket,)*
+            "{}",
+            #msg
+        );
+    }
+}
+
+/// A `log` record with the structured key-values of the TODO, it needs the `kv` feature of `log`
+fn log_kv_event(msg: &str, todo: &Todo) -> TokenStream {
+    let name = todo.kind.name();
+    // `log` doesn't take a trailing comma before the `;`
+    let mut kvs = vec![quote!(kind = #name)];
+    if let Kind::By(Some(deadline)) = todo.kind {
+        let rfc3339 = fmt_rfc3339(deadline);
+        kvs.push(quote!(deadline = #rfc3339));
+        // The clock errors are reported by the `by` condition
+        if let Ok(Some(now)) = runtime_now() {
+            kvs.push(quote!(overdue_secs = #now.saturating_sub(#deadline)));
+        }
+    }
+    kvs.push(quote!(file = ::core::file!()));
+    kvs.push(quote!(line = ::core::line!()));
+    if let Some(owner) = &todo.meta.owner {
+        kvs.push(quote!(owner = #owner));
+    }
+    if let Some(ticket) = &todo.meta.ticket {
+        kvs.push(quote!(ticket = #ticket));
+    }
+    quote! {
+        ::log::error!(#(#kvs),*; "{}", #msg);
+    }
+}
+
+/// The error of the `eprint` action with the `no-std` feature
+const NO_STD_EPRINT: &str =
+    "The `eprint` action needs `std`, it can't be used with the `no-std` feature";
+
+/// Prints a line to the standard error, only the first time that the call site is met in the process, so a loop doesn't flood the terminal
+fn eprint_once(msg: &str, todo: &Todo) -> TokenStream {
+    let message = todo.message;
+    let print = match todo.kind {
+        Kind::By(Some(deadline)) => {
+            let rfc3339 = fmt_rfc3339(deadline);
+            let since = match rfc3339.strip_suffix("T00:00:00Z") {
+                Some(date) => date.to_string(),
+                None => format!("{} {} UTC", &rfc3339[..10], &rfc3339[11..16]),
+            };
+            let now = match runtime_now() {
+                Ok(Some(now)) => now,
+                Ok(None) => return quote!(::core::compile_error!(#NO_STD_EPRINT)),
+                Err(e) => return quote!(::core::compile_error!(#e)),
+            };
+            quote! {
+                let days = #now.saturating_sub(#deadline) / 86_400;
+                ::std::eprintln!(
+                    "todo2: overdue since {} ({} day{}): {} at {}:{}",
+                    #since,
+                    days,
+                    if days == 1 { "" } else { "s" },
+                    #message,
+                    ::core::file!(),
+                    ::core::line!()
+                );
+            }
+        }
+        Kind::By(None) => quote! {
+            ::std::eprintln!("todo2: overdue: {} at {}:{}", #message, ::core::file!(), ::core::line!());
+        },
+        _ => {
+            let msg = msg.strip_prefix("TODO: ").unwrap_or(msg);
+            quote! {
+                ::std::eprintln!("todo2: {} at {}:{}", #msg, ::core::file!(), ::core::line!());
+            }
+        }
+    };
+    quote! {
+        static TODO2_PRINTED: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
+        if !TODO2_PRINTED.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+            #print
+        }
+    }
+}
+
+fn unknown_at_build() -> TokenStream {
+    quote!(::core::compile_error!(
+        "The `warn` action can't be used with a condition that's only known at runtime"
+    );)
+}
+
+/// Parses the action name, e.g. `panic` or `compile_error`
+pub(crate) fn parse_action(tokens: &mut Peekable<IntoIter>) -> Result<Action> {
+    let Some(TokenTree::Ident(ident)) = tokens.next() else {
+        return Err("Expected an action after `action:`".to_string());
+    };
+    Ok(match ident.to_string().as_str() {
+        "panic" => Action::Panic,
+        "log" => Action::Log,
+        "tracing" => Action::Tracing,
+        "eprint" => Action::Eprint,
+        "compile_error" => Action::CompileError,
+        "warn" => Action::Warn,
+        "ignore" => Action::Ignore,
+        action => {
+            return Err(format!(
+                "Unknown action `{}`, expected `panic`, `log`, `tracing`, `eprint`, `compile_error`, `warn` or `ignore`",
+                action
+            ))
+        }
+    })
+}
+
+/// Emits a warning at the macro call site
+#[cfg(feature = "nightly")]
+pub(crate) fn warning(note: &str) -> TokenStream {
+    proc_macro::Span::call_site().warning(note).emit();
+    quote!()
+}
+
+/// Emits a warning at the macro call site
+#[cfg(not(feature = "nightly"))]
+pub(crate) fn warning(note: &str) -> TokenStream {
+    deprecated_warning(note)
+}
+
+/// A warning that's a use of a deprecated function, since the stable compiler doesn't let the macros emit warnings.
+///
+/// Unlike the `proc_macro::Diagnostic` one, it can be behind a `cfg`.
+fn deprecated_warning(note: &str) -> TokenStream {
+    quote! {{
+        #[deprecated(note = #note)]
+        fn todo2_warning() {}
+        todo2_warning();
+    }}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_tokens;
+
+    fn todo(kind: Kind, meta: &Metadata) -> Todo<'_> {
+        Todo {
+            message: "TODO",
+            kind,
+            meta,
+        }
+    }
+
+    fn runtime(cond: TokenStream, met_at_build: Option<bool>) -> Check {
+        Check::Runtime { cond, met_at_build }
+    }
+
+    fn assert_emits(action: Action, check: Check, expected: TokenStream) {
+        assert_eq!(
+            action
+                .emit(check, "TODO", &todo(Kind::If, &Metadata::default()))
+                .to_string(),
+            expected.to_string(),
+            "{:?}",
+            action
+        );
+    }
+
+    #[test]
+    fn test_parse_action() -> Result<()> {
+        assert_eq!(parse_tokens(parse_action, quote!(panic))?, Action::Panic);
+        assert_eq!(parse_tokens(parse_action, quote!(log))?, Action::Log);
+        assert_eq!(
+            parse_tokens(parse_action, quote!(tracing))?,
+            Action::Tracing
+        );
+        assert_eq!(parse_tokens(parse_action, quote!(eprint))?, Action::Eprint);
+        assert_eq!(
+            parse_tokens(parse_action, quote!(compile_error))?,
+            Action::CompileError
+        );
+        assert_eq!(parse_tokens(parse_action, quote!(warn))?, Action::Warn);
+        assert_eq!(parse_tokens(parse_action, quote!(ignore))?, Action::Ignore);
+        assert!(parse_tokens(parse_action, quote!(error)).is_err());
+        assert!(parse_tokens(parse_action, quote!("panic")).is_err());
+        assert!(parse_tokens(parse_action, quote!()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_runtime_checks() {
+        let action = Action::Panic;
+        assert_emits(
+            action,
+            Check::Met,
+            quote!(if true {
+                ::core::panic!("{}", "TODO");
+            }),
+        );
+        assert_emits(
+            action,
+            Check::Cfg(quote!(unix)),
+            quote!(if ::core::cfg!(unix) {
+                ::core::panic!("{}", "TODO");
+            }),
+        );
+        assert_emits(
+            action,
+            Check::Const(quote!(N > 1)),
+            quote!(if N > 1 {
+                ::core::panic!("{}", "TODO");
+            }),
+        );
+        for met_at_build in [None, Some(false), Some(true)] {
+            assert_emits(
+                action,
+                runtime(quote!(a == 1), met_at_build),
+                quote!(if a == 1 {
+                    ::core::panic!("{}", "TODO");
+                }),
+            );
+        }
+    }
+
+    #[test]
+    fn test_runtime_sinks() {
+        #[cfg(not(feature = "log-kv"))]
+        assert_emits(
+            Action::Log,
+            Check::Met,
+            quote!(if true {
+                ::log::error!("{}", "TODO");
+            }),
+        );
+        #[cfg(feature = "log-kv")]
+        assert_emits(
+            Action::Log,
+            Check::Met,
+            quote!(if true {
+                ::log::error!(kind = "if", file = ::core::file!(), line = ::core::line!(); "{}", "TODO");
+            }),
+        );
+        assert_emits(
+            Action::Tracing,
+            Check::Met,
+            quote!(if true {
+                ::tracing::error!(
+                    todo.message = "TODO",
+                    todo.kind = "if",
+                    todo.file = ::core::file!(),
+                    todo.line = ::core::line!(),
+                    "{}",
+                    "TODO"
+                );
+            }),
+        );
+        #[cfg(not(feature = "no-std"))]
+        assert_emits(
+            Action::Eprint,
+            Check::Met,
+            quote!(if true {
+                static TODO2_PRINTED: ::core::sync::atomic::AtomicBool =
+                    ::core::sync::atomic::AtomicBool::new(false);
+                if !TODO2_PRINTED.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+                    ::std::eprintln!(
+                        "todo2: {} at {}:{}",
+                        "TODO",
+                        ::core::file!(),
+                        ::core::line!()
+                    );
+                }
+            }),
+        );
+        #[cfg(feature = "no-std")]
+        assert_emits(
+            Action::Eprint,
+            Check::Met,
+            quote!(if true {
+                ::core::compile_error!(
+                    "The `eprint` action needs `std`, it can't be used with the `no-std` feature"
+                );
+            }),
+        );
+    }
+
+    #[test]
+    fn test_compile_error_sink() {
+        let action = Action::CompileError;
+        let error = quote!(::core::compile_error!("TODO"););
+        assert_emits(action, Check::Met, error.clone());
+        assert_emits(
+            action,
+            Check::Cfg(quote!(unix)),
+            quote!(#[cfg(unix)] ::core::compile_error!("TODO");),
+        );
+        assert_emits(
+            action,
+            Check::Const(quote!(N > 1)),
+            quote!(
+                const _: () = ::core::assert!(!(N > 1), "TODO");
+            ),
+        );
+        assert_emits(action, runtime(quote!(a), Some(true)), error);
+        // falls back to a panic
+        let panic = quote!(if a {
+            ::core::panic!("{}", "TODO");
+        });
+        assert_emits(action, runtime(quote!(a), Some(false)), panic.clone());
+        assert_emits(action, runtime(quote!(a), None), panic);
+    }
+
+    #[test]
+    fn test_warn_sink() {
+        let action = Action::Warn;
+        let warning = deprecated_warning("TODO");
+        #[cfg(not(feature = "nightly"))]
+        assert_emits(action, Check::Met, warning.clone());
+        assert_emits(
+            action,
+            Check::Cfg(quote!(unix)),
+            quote!(#[cfg(unix)] #warning),
+        );
+        assert_emits(action, Check::Const(quote!(N > 1)), unknown_at_build());
+        #[cfg(not(feature = "nightly"))]
+        assert_emits(action, runtime(quote!(a), Some(true)), warning);
+        assert_emits(action, runtime(quote!(a), Some(false)), quote!());
+        assert_emits(action, runtime(quote!(a), None), unknown_at_build());
+    }
+
+    #[test]
+    fn test_ignore_sink() {
+        for check in [
+            Check::Met,
+            Check::Cfg(quote!(unix)),
+            Check::Const(quote!(N > 1)),
+            runtime(quote!(a), Some(true)),
+            runtime(quote!(a), None),
+        ] {
+            assert_emits(Action::Ignore, check, quote!());
+        }
+    }
+
+    #[test]
+    fn test_tracing_fields() {
+        let meta = Metadata {
+            owner: Some("alice".to_string()),
+            ticket: None,
+        };
+        let code = Action::Tracing
+            .emit(
+                Check::Met,
+                "TODO",
+                &todo(Kind::By(Some(1_727_740_800)), &meta),
+            )
+            .to_string();
+        assert!(
+            code.contains(r#"todo . deadline = "2024-10-01T00:00:00Z""#),
+            "{}",
+            code
+        );
+        assert!(code.contains(r#"todo . kind = "by""#), "{}", code);
+        assert!(code.contains(r#"todo . owner = "alice""#), "{}", code);
+        assert!(!code.contains("ticket"), "{}", code);
+        let code = Action::Tracing
+            .emit(
+                Check::Met,
+                "TODO",
+                &todo(Kind::By(None), &Metadata::default()),
+            )
+            .to_string();
+        assert!(!code.contains("deadline"), "{}", code);
+
+        let code = tracing_warning(quote!(a), "TODO", &todo(Kind::By(Some(0)), &meta)).to_string();
+        assert!(code.starts_with("if a { :: tracing :: warn !"), "{}", code);
+        assert!(
+            code.contains(r#"todo . deadline = "1970-01-01T00:00:00Z""#),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_log_kv_fields() {
+        let meta = Metadata {
+            owner: None,
+            ticket: Some("OPS-42".to_string()),
+        };
+        let code = log_kv_event("TODO", &todo(Kind::By(Some(1_727_740_800)), &meta)).to_string();
+        assert!(
+            code.starts_with(r#":: log :: error ! (kind = "by" ,"#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"deadline = "2024-10-01T00:00:00Z" ,"#),
+            "{}",
+            code
+        );
+        // the overdue seconds need a clock
+        #[cfg(not(feature = "no-std"))]
+        assert!(
+            code.contains(". saturating_sub (1727740800u64)"),
+            "{}",
+            code
+        );
+        assert!(
+            code.ends_with(r#"ticket = "OPS-42" ; "{}" , "TODO") ;"#),
+            "{}",
+            code
+        );
+        assert!(!code.contains("owner"), "{}", code);
+
+        let code = log_kv_event("TODO", &todo(Kind::Env, &Metadata::default())).to_string();
+        assert!(!code.contains("deadline"), "{}", code);
+        assert!(code.contains(r#"kind = "env""#), "{}", code);
+    }
+
+    #[cfg(not(feature = "no-std"))]
+    #[test]
+    fn test_eprint_line() {
+        let meta = Metadata::default();
+        let code = eprint_once("TODO", &todo(Kind::By(Some(1_727_740_800)), &meta)).to_string();
+        assert!(code.contains("static TODO2_PRINTED"), "{}", code);
+        assert!(
+            code.contains(r#""todo2: overdue since {} ({} day{}): {} at {}:{}" , "2024-10-01""#),
+            "{}",
+            code
+        );
+        let code = eprint_once("TODO", &todo(Kind::By(Some(1_727_775_000)), &meta)).to_string();
+        assert!(code.contains(r#""2024-10-01 09:30 UTC""#), "{}", code);
+        let code = eprint_once("TODO: Hack NASA", &todo(Kind::If, &meta)).to_string();
+        assert!(
+            code.contains(r#""todo2: {} at {}:{}" , "Hack NASA""#),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_message_isnt_a_format_string() {
+        let code = Action::Panic
+            .emit(
+                Check::Met,
+                "TODO: {x}",
+                &todo(Kind::If, &Metadata::default()),
+            )
+            .to_string();
+        assert!(code.contains(r#"panic ! ("{}" , "TODO: {x}")"#), "{}", code);
+        let code = Action::CompileError
+            .emit(
+                Check::Const(quote!(true)),
+                "TODO: {x}",
+                &todo(Kind::ConstIf, &Metadata::default()),
+            )
+            .to_string();
+        assert!(code.contains(r#""TODO: {{x}}""#), "{}", code);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::dep::lockfile_in;
+use crate::{manifest_dir, Result};
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The variable that sets the file that keeps the anchors, a relative path starts from the `Cargo.toml` of the crate
+pub(crate) const ANCHORS_ENV: &str = "TODO2_ANCHORS";
+
+/// The file that keeps the anchors by default, in the `target` directory of the workspace,
+/// so the profiles, the features and the compilers of the crate all share it, and it's never in the source tree
+const ANCHORS_FILE: &str = "todo2-anchors";
+
+/// Returns the file of the macro call site, the build script enables it with rustc 1.88 or newer
+#[cfg(todo2_span_file)]
+#[clippy::msrv = "1.88"]
+pub(crate) fn call_site_file() -> String {
+    proc_macro::Span::call_site().file()
+}
+
+/// Returns the file of the macro call site, it's unknown before rustc 1.88,
+/// so the same message in two files shares one anchor unless they have different `id`s
+#[cfg(not(todo2_span_file))]
+pub(crate) fn call_site_file() -> String {
+    String::new()
+}
+
+/// Returns the time stamp that the relative deadline starts from,
+/// that's when the macro was first expanded with the same crate, file, message and id, or `now` if it's the first time
+pub(crate) fn anchor(file: &str, msg: &str, id: Option<&str>, now: u64) -> Result<u64> {
+    let krate = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+    anchor_in(
+        &anchors_file()?,
+        &[&krate, file, msg, id.unwrap_or_default()],
+        now,
+    )
+}
+
+fn anchors_file() -> Result<PathBuf> {
+    let dir = manifest_dir()?;
+    match std::env::var_os(ANCHORS_ENV) {
+        // joining an absolute path replaces the directory
+        Some(path) => Ok(dir.join(path)),
+        None => Ok(target_dir(&dir, std::env::var_os("CARGO_TARGET_DIR")).join(ANCHORS_FILE)),
+    }
+}
+
+/// Returns the `target` directory of the workspace, next to its `Cargo.lock`, or `CARGO_TARGET_DIR` if it's set
+fn target_dir(manifest_dir: &Path, target_dir: Option<OsString>) -> PathBuf {
+    let root = lockfile_in(manifest_dir)
+        .and_then(|lockfile| lockfile.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| manifest_dir.to_path_buf());
+    root.join(target_dir.unwrap_or_else(|| "target".into()))
+}
+
+/// Finds the anchor of the key in the file, or records it with the given time stamp.
+///
+/// Each line is the time stamp then the escaped parts of the key, separated by tabs.
+fn anchor_in(path: &Path, key: &[&str], now: u64) -> Result<u64> {
+    let key = key.iter().map(|k| escape(k)).collect::<Vec<_>>().join("\t");
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Can't read `{}`: {}", path.display(), e)),
+    };
+    for line in content.lines() {
+        let Some((time_stamp, line_key)) = line.split_once('\t') else {
+            continue;
+        };
+        if line_key == key {
+            return time_stamp
+                .parse()
+                .map_err(|_| format!("Invalid anchor `{}` in `{}`", time_stamp, path.display()));
+        }
+    }
+
+    let mut line = String::new();
+    if content.is_empty() {
+        line.push_str("# The time stamps that the `in` conditions of `todo2` start from\n");
+    } else if !content.ends_with('\n') {
+        line.push('\n');
+    }
+    line.push_str(&format!("{}\t{}\n", now, key));
+    // A single append, so the parallel builds don't overwrite each other
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Can't write `{}`: {}", path.display(), e))?;
+    Ok(now)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_in() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("todo2-anchors-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let key = ["app", "src/main.rs", "Remove the hack", ""];
+        assert_eq!(anchor_in(&path, &key, 1_000)?, 1_000);
+        // the first expansion wins
+        assert_eq!(anchor_in(&path, &key, 2_000)?, 1_000);
+        let other = ["app", "src/main.rs", "Remove the hack", "cache"];
+        assert_eq!(anchor_in(&path, &other, 3_000)?, 3_000);
+        let tricky = ["app", "src/lib.rs", "Tabs\tand\nnew lines", ""];
+        assert_eq!(anchor_in(&path, &tricky, 4_000)?, 4_000);
+        assert_eq!(anchor_in(&path, &tricky, 5_000)?, 4_000);
+        assert_eq!(anchor_in(&path, &key, 6_000)?, 1_000);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 4);
+        assert!(content.contains("4000\tapp\tsrc/lib.rs\tTabs\\tand\\nnew lines\t\n"));
+        std::fs::remove_file(&path).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_dir() {
+        let workspace =
+            std::env::temp_dir().join(format!("todo2-workspace-{}", std::process::id()));
+        let member = workspace.join("app");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(workspace.join("Cargo.lock"), "").unwrap();
+
+        assert_eq!(target_dir(&member, None), workspace.join("target"));
+        assert_eq!(
+            target_dir(&member, Some("out".into())),
+            workspace.join("out")
+        );
+        let absolute = std::env::temp_dir().join("todo2-target");
+        assert_eq!(target_dir(&member, Some(absolute.clone().into())), absolute);
+        std::fs::remove_dir_all(&workspace).unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+use crate::Result;
+use proc_macro2::TokenStream;
+use quote::quote;
+use todo2_runtime::parse_now;
+
+/// The variable with the path of the clock function, for the `no-std` feature
+pub(crate) const CLOCK_ENV: &str = "TODO2_CLOCK";
+
+/// The variable that overrides the clock of the macro, it takes precedence over `SOURCE_DATE_EPOCH`
+pub(crate) const BUILD_NOW_ENV: &str = "TODO2_BUILD_NOW";
+
+/// The time stamp of reproducible builds, see <https://reproducible-builds.org/specs/source-date-epoch/>
+pub(crate) const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+/// Returns the current unix time stamp, when the macro is expanded.
+///
+/// It's `TODO2_BUILD_NOW` or `SOURCE_DATE_EPOCH` if they're set, so the builds are reproducible.
+pub(crate) fn compile_time_now() -> Result<u64> {
+    build_now(
+        std::env::var(BUILD_NOW_ENV).ok().as_deref(),
+        std::env::var(SOURCE_DATE_EPOCH_ENV).ok().as_deref(),
+    )
+}
+
+fn build_now(build_now: Option<&str>, source_date_epoch: Option<&str>) -> Result<u64> {
+    let build_now = build_now.map(str::trim).filter(|now| !now.is_empty());
+    if let Some(now) = build_now {
+        return parse_now(now).ok_or_else(|| {
+            format!(
+                "Invalid `{}` `{}`, expected a unix time stamp or a date like `2026-01-01T00:00:00Z`",
+                BUILD_NOW_ENV, now
+            )
+        });
+    }
+    let source_date_epoch = source_date_epoch
+        .map(str::trim)
+        .filter(|epoch| !epoch.is_empty());
+    if let Some(epoch) = source_date_epoch {
+        return epoch.parse().map_err(|_| {
+            format!(
+                "Invalid `{}` `{}`, expected a unix time stamp",
+                SOURCE_DATE_EPOCH_ENV, epoch
+            )
+        });
+    }
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs())
+}
+
+/// The current unix time stamp at runtime, from the same clock as the `with-chrono` and `with-time` deadlines.
+///
+/// It's `None` with the `no-std` feature when there's no clock function, then the deadlines are only checked at compile time.
+pub(crate) fn runtime_now() -> Result<Option<TokenStream>> {
+    let now = clock()?;
+    if cfg!(feature = "test-clock") {
+        return Ok(now.map(overridable));
+    }
+    Ok(now)
+}
+
+fn clock() -> Result<Option<TokenStream>> {
+    if cfg!(feature = "no-std") {
+        user_clock(std::env::var(CLOCK_ENV).ok().as_deref())
+    } else if cfg!(feature = "with-chrono") {
+        Ok(Some(quote!((::chrono::Utc::now().timestamp() as u64))))
+    } else if cfg!(feature = "with-time") {
+        Ok(Some(quote!(
+            (::time::OffsetDateTime::now_utc().unix_timestamp() as u64)
+        )))
+    } else {
+        Ok(Some(quote!(::std::time::SystemTime::now()
+            .duration_since(::std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs())))
+    }
+}
+
+/// Calls the clock function at the path, e.g. `crate::rtc::unix_time`, it returns the unix time stamp as `u64`
+fn user_clock(path: Option<&str>) -> Result<Option<TokenStream>> {
+    let Some(path) = path.map(str::trim).filter(|path| !path.is_empty()) else {
+        return Ok(None);
+    };
+    let is_path = path.split("::").enumerate().all(|(i, segment)| {
+        // `::my_crate::now` starts with an empty segment
+        (i == 0 && segment.is_empty())
+            || segment.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    if !is_path {
+        return Err(format!(
+            "Expected the path of a function in the `{}` variable, e.g. `crate::rtc::unix_time`, found `{}`",
+            CLOCK_ENV, path
+        ));
+    }
+    let path = path.parse::<TokenStream>().map_err(|e| e.to_string())?;
+    Ok(Some(quote!({
+        let now: u64 = #path();
+        now
+    })))
+}
+
+/// Reads the time stamp from `TODO2_NOW` through the runtime crate if it's set, otherwise it's the given clock
+fn overridable(clock: TokenStream) -> TokenStream {
+    quote!(match ::todo2_runtime::now_override() {
+        ::core::option::Option::Some(now) => now,
+        ::core::option::Option::None => #clock,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_clock() -> Result<()> {
+        assert!(user_clock(None)?.is_none());
+        assert!(user_clock(Some(" "))?.is_none());
+        let clock = user_clock(Some("crate::rtc::unix_time"))?.unwrap();
+        assert_eq!(
+            clock.to_string(),
+            "{ let now : u64 = crate :: rtc :: unix_time () ; now }"
+        );
+        assert!(user_clock(Some("::rtc::now"))?.is_some());
+        assert!(user_clock(Some("todo2_clock"))?.is_some());
+        assert!(user_clock(Some("now()")).is_err());
+        assert!(user_clock(Some("rtc::")).is_err());
+        assert!(user_clock(Some("rtc:now")).is_err());
+        assert!(user_clock(Some("1now")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_now() -> Result<()> {
+        assert_eq!(build_now(Some("1767225600"), None)?, 1_767_225_600);
+        assert_eq!(build_now(Some("2026-01-01"), Some("0"))?, 1_767_225_600);
+        assert_eq!(build_now(None, Some("1767225600"))?, 1_767_225_600);
+        assert_eq!(build_now(Some(" "), Some(" 0 "))?, 0);
+        // the wall clock when neither is set
+        let before = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(build_now(None, Some(""))? >= before);
+        assert!(build_now(None, None)? >= before);
+        assert!(build_now(Some("tomorrow"), None).is_err());
+        assert!(build_now(None, Some("2026-01-01")).is_err());
+        assert!(build_now(None, Some("-1")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_overridable() {
+        assert_eq!(
+            overridable(quote!(now())).to_string(),
+            quote!(match ::todo2_runtime::now_override() {
+                ::core::option::Option::Some(now) => now,
+                ::core::option::Option::None => now(),
+            })
+            .to_string()
+        );
+    }
+}
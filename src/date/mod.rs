@@ -1,53 +1,214 @@
+#[cfg(feature = "and-time")]
+mod time_of_day;
 mod unix_time;
 
-use crate::date::unix_time::{UnixTimeCalc, ONE_HOUR};
+#[cfg(feature = "and-time")]
+use crate::date::time_of_day::TimeOfDay;
+use crate::date::unix_time::{month_number, Offset, UnixTimeCalc, ONE_HOUR};
 use crate::Result;
 use proc_macro2::token_stream::IntoIter;
-use proc_macro2::TokenTree;
+#[cfg(any(feature = "with-chrono", feature = "with-time"))]
+use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenTree};
+#[cfg(any(feature = "with-chrono", feature = "with-time"))]
+use quote::TokenStreamExt;
 use std::iter::Peekable;
+use todo2_runtime::date::{civil_from_days, Rfc3339};
+
+#[cfg(feature = "chrono-backend")]
+type Calc = unix_time::chrono::ChronoCalc;
+#[cfg(feature = "time-backend")]
+type Calc = unix_time::time::TimeCalc;
+#[cfg(not(any(feature = "chrono-backend", feature = "time-backend")))]
+type Calc = unix_time::simple::SimpleCalc;
 
 pub(crate) fn parse_date(tokens: &mut Peekable<IntoIter>) -> Result<u64> {
-    #[cfg(feature = "chrono-backend")]
-    let un_calc = unix_time::chrono::ChronoCalc::new();
-    #[cfg(feature = "time-backend")]
-    let un_calc = unix_time::time::TimeCalc::new();
-    #[cfg(not(any(feature = "chrono-backend", feature = "time-backend")))]
-    let un_calc = unix_time::simple::SimpleCalc::new();
+    _parse(tokens, Calc::new())
+}
+
+/// Parses a deadline in a string literal, in the ISO 8601 extended format that RFC 3339 uses,
+/// e.g. `"2025-03-26"` or `"2025-03-26T09:00:00+02:00"`
+pub(crate) fn parse_date_str(date: &str) -> Result<u64> {
+    _parse_str(date, Calc::new())
+}
+
+/// Checks if the `by` condition is an expression instead of a raw date,
+/// the raw date starts with a number, or with a month name before the year, e.g. `March 2025`
+#[cfg(any(feature = "with-chrono", feature = "with-time"))]
+pub(crate) fn is_date_expr(tokens: &mut Peekable<IntoIter>) -> bool {
+    let mut ahead = tokens.clone();
+    match ahead.next() {
+        Some(TokenTree::Literal(_)) | None => false,
+        Some(TokenTree::Ident(ident)) if month_number(&ident.to_string()).is_some() => {
+            !matches!(ahead.next(), Some(TokenTree::Literal(_)))
+        }
+        _ => true,
+    }
+}
+
+/// Checks if the raw date of the `by` condition is in the build machine time zone, that depends on the `TZ` variable
+pub(crate) fn is_local_date(tokens: &Peekable<IntoIter>) -> bool {
+    tokens
+        .clone()
+        .take_while(|token| !is_separator(token))
+        .any(|token| matches!(token, TokenTree::Ident(ident) if ident == "local"))
+}
+
+/// Takes the deadline expression as is, it gets evaluated at runtime
+#[cfg(any(feature = "with-chrono", feature = "with-time"))]
+pub(crate) fn parse_date_expr(tokens: &mut Peekable<IntoIter>) -> Result<TokenStream> {
+    let mut rt = TokenStream::new();
+    for nt in tokens.by_ref() {
+        if is_separator(&nt) {
+            break;
+        }
+        rt.append(nt);
+    }
+    if rt.is_empty() {
+        return Err("Expected a deadline after `by:`".to_string());
+    }
+    Ok(rt)
+}
 
-    _parse(tokens, un_calc)
+/// Evaluates a `time::macros::datetime!` invocation at expansion time, so it works with the `compile-error` feature.
+///
+/// Returns `None` if the expression isn't a `datetime!` invocation,
+/// or if it uses a syntax that our parser doesn't understand, e.g. the ordinal dates or the fractions of a second,
+/// then `time` checks it and it's compared at runtime.
+/// Returns an error if the date or the time is invalid, or if the time is missing, because `datetime!` rejects them too.
+#[cfg(feature = "with-time")]
+pub(crate) fn eval_datetime_macro(expr: &TokenStream) -> Result<Option<u64>> {
+    let tokens = expr.clone().into_iter().collect::<Vec<_>>();
+    let [path @ .., TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(args)] =
+        tokens.as_slice()
+    else {
+        return Ok(None);
+    };
+    let is_path = path.iter().all(|t| match t {
+        TokenTree::Ident(_) => true,
+        TokenTree::Punct(punct) => punct.as_char() == ':',
+        _ => false,
+    });
+    if name != "datetime" || bang.as_char() != '!' || !is_path {
+        return Ok(None);
+    }
+    let args = args.stream().into_iter().collect::<Vec<_>>();
+    // the years with a sign or more digits need the `large-dates` feature of `time`
+    if !matches!(args.first(), Some(TokenTree::Literal(year)) if year.to_string().len() == 4) {
+        return Ok(None);
+    }
+    let is_dash = |t: &TokenTree| matches!(t, TokenTree::Punct(p) if p.as_char() == '-');
+    // `YYYY-MM-DD` or `YYYY-W WW-D`, the ordinal dates are left to `time`
+    let mut date = TokenStream::new();
+    let time = match args.as_slice() {
+        [_, d1, TokenTree::Literal(_), d2, TokenTree::Literal(_), time @ ..]
+            if is_dash(d1) && is_dash(d2) =>
+        {
+            date.extend(args[..5].iter().cloned());
+            time
+        }
+        [_, d1, TokenTree::Ident(w), TokenTree::Literal(week), d2, TokenTree::Literal(_), time @ ..]
+            if w == "W" && is_dash(d1) && is_dash(d2) =>
+        {
+            date.extend(args[..2].iter().cloned());
+            date.append(proc_macro2::Ident::new(&format!("W{}", week), week.span()));
+            date.extend(args[4..6].iter().cloned());
+            time
+        }
+        _ => return Ok(None),
+    };
+    let Some(TokenTree::Literal(hour)) = time.first() else {
+        return Err(
+            "Expected the time after the date, e.g. `datetime!(2024-10-01 09:00 UTC)`".to_string(),
+        );
+    };
+    let sign = time
+        .iter()
+        .position(|t| matches!(t, TokenTree::Punct(p) if p.as_char() == '+' || p.as_char() == '-'));
+    let offset_parts = sign.map_or(0, |sign| {
+        time[sign..].iter().filter(|t| is_colon(t)).count()
+    });
+    if offset_parts > 1 || time.iter().any(|t| t.to_string().contains('.')) {
+        return Ok(None);
+    }
+    let is_meridiem = |t: &TokenTree| matches!(t, TokenTree::Ident(i) if ["am", "AM", "pm", "PM"].iter().any(|m| i == m));
+    let has_minutes = time.get(1).map_or(false, |t| is_colon(t) || is_meridiem(t));
+    if !has_minutes {
+        return Err(format!(
+            "Expected the minutes or `am`/`pm` after the hour `{}`, e.g. `09:00` or `9 am`",
+            hour
+        ));
+    }
+    if let Some(word) = time
+        .iter()
+        .find(|t| matches!(t, TokenTree::Ident(i) if !is_meridiem(t) && i != "UTC" && i != "utc"))
+    {
+        return Err(format!("Unexpected `{}` in `datetime!`", word));
+    }
+    // `datetime!` doesn't have the `at` between the date and the time, so we put it before the hour
+    date.append(proc_macro2::Ident::new("at", hour.span()));
+    date.extend(time.iter().cloned());
+    parse_date(&mut date.into_iter().peekable()).map(Some)
+}
+
+#[cfg(feature = "with-time")]
+fn is_colon(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == ':')
+}
+
+/// The parts of the date that take the place of the month and the day
+enum Period {
+    /// `2025-Q3`, it's the first month of the quarter
+    Quarter,
+    /// `2025-W12` or `2025-W12-3`, the week day is Monday if it isn't set
+    Week { week: u32, weekday: Option<u32> },
 }
 
 fn _parse<C: UnixTimeCalc>(tokens: &mut Peekable<IntoIter>, mut un_calc: C) -> Result<u64> {
-    let mut time_stamp = 0;
-    #[cfg(feature = "and-time")]
-    let mut parsing_date = true;
+    let mut offset = None;
+    let mut period = None;
+    // The day of `15 Mar 2025`, it's checked after the year
+    let mut day_first = None;
+    // It's set after `at` or `@`, then the rest is the time of the day
     #[cfg(feature = "and-time")]
-    let mut hour_not_set = true;
-    #[cfg(feature = "and-time")]
-    let mut minute_not_set = true;
-    for nt in tokens.by_ref() {
+    let mut time: Option<TimeOfDay> = None;
+    // The span of the previous token, and the most digits of the next part of the date if it can follow a `-`,
+    // e.g. 2 for the month after the year, or 1 for the weekday after the week
+    let mut prev: Option<Span> = None;
+    let mut next_part: Option<usize> = None;
+    while let Some(nt) = tokens.next() {
+        let span = nt.span();
+        let before = prev.replace(span);
+        let digits = next_part.take();
+        if offset.is_some() && !is_separator(&nt) {
+            return Err(format!(
+                "Unexpected `{}`, the offset must be the last part of the deadline",
+                nt
+            ));
+        }
         match nt {
             TokenTree::Punct(punct) => {
                 let punct = punct.as_char();
+                if punct == '+' || (punct == '-' && un_calc.is_day_set()) {
+                    offset = Some(parse_offset(punct, tokens)?);
+                    continue;
+                }
                 if punct == '-' {
                     #[cfg(feature = "and-time")]
-                    if !parsing_date {
-                        return Err(format!("Unexpected `{}`, you can't use `-` in the time part only use `:` or `.`", punct));
+                    if time.is_some() {
+                        offset = Some(parse_offset(punct, tokens)?);
+                        continue;
+                    }
+                    if !is_date_dash(before, span, digits, tokens)? {
+                        offset = Some(parse_offset(punct, tokens)?);
                     }
                     continue;
                 }
                 #[cfg(feature = "and-time")]
                 if punct == '@' {
-                    if !parsing_date {
-                        return Err(
-                            "You've already specified the time, you can't specify it again"
-                                .to_string(),
-                        );
-                    }
-                    parsing_date = false;
-                    time_stamp = un_calc.calc()?;
+                    time = Some(start_time(time, &un_calc)?);
                     continue;
-                } else if !parsing_date && punct == ':' {
+                } else if time.is_some() && punct == ':' {
                     continue;
                 }
                 if punct == ',' || punct == ';' {
@@ -59,34 +220,55 @@ fn _parse<C: UnixTimeCalc>(tokens: &mut Peekable<IntoIter>, mut un_calc: C) -> R
             TokenTree::Literal(lit) => {
                 let lit = lit.to_string();
                 #[cfg(feature = "and-time")]
-                if !parsing_date {
-                    if time_stamp == 0 {
-                        return Err("You must at least specify year in the date part before specifying the time :)".to_string());
-                    }
-                    let n = lit
-                        .parse::<u64>()
-                        .map_err(|e| format!("Invalid number `{}`: {}", lit, e))?;
-                    if hour_not_set {
-                        if n > 23 {
-                            return Err(format!("Invalid hour `{}`, it must be less than 24", n));
+                if let Some(time) = time.as_mut() {
+                    // `9:00Z` is tokenized as `9`, `:` and `00Z`
+                    let lit = match lit.strip_suffix('Z') {
+                        Some(lit) => {
+                            offset = Some(Offset::Fixed(0));
+                            lit
                         }
-                        time_stamp += n * ONE_HOUR;
-                        hour_not_set = false
-                    } else if minute_not_set {
-                        if n > 59 {
-                            return Err(format!("Invalid minute `{}`", n));
-                        }
-                        time_stamp += n * 60;
-                        minute_not_set = false;
-                    } else {
-                        return Err(format!("Unexpected `{}`", lit));
+                        None => lit.as_str(),
+                    };
+                    time.number(lit)?;
+                    continue;
+                }
+                match &mut period {
+                    Some(Period::Week {
+                        weekday: weekday @ None,
+                        ..
+                    }) => {
+                        *weekday = Some(
+                            lit.parse::<u32>()
+                                .map_err(|_| format!("Invalid weekday `{}`", lit))?,
+                        );
+                        continue;
+                    }
+                    Some(_) => {
+                        return Err(format!(
+                            "Unexpected `{}` after the week or the quarter",
+                            lit
+                        ))
                     }
+                    None => {}
+                }
+                // `15 Mar 2025` starts with the day, the year has four digits
+                let day_before_month = lit.len() <= 2
+                    && matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if month_number(&ident.to_string()).is_some());
+                if day_before_month && !un_calc.is_year_set() && day_first.is_none() {
+                    day_first = Some(lit);
                     continue;
                 }
                 if !un_calc.is_year_set() {
                     un_calc.year(lit)?;
+                    if let Some(day) = day_first.take() {
+                        un_calc.day(day)?;
+                    }
+                    if !un_calc.is_month_set() {
+                        next_part = Some(2);
+                    }
                 } else if !un_calc.is_month_set() {
                     un_calc.month(lit)?;
+                    next_part = Some(2);
                 } else if !un_calc.is_day_set() {
                     un_calc.day(lit)?;
                 } else {
@@ -94,18 +276,54 @@ fn _parse<C: UnixTimeCalc>(tokens: &mut Peekable<IntoIter>, mut un_calc: C) -> R
                 }
             }
             TokenTree::Ident(ident) => {
-                #[cfg(feature = "and-time")]
-                if ident == "at" {
-                    if !parsing_date {
+                match ident.to_string().as_str() {
+                    "Z" | "UTC" => {
+                        offset = Some(Offset::Fixed(0));
+                        continue;
+                    }
+                    "local" => {
+                        offset = Some(Offset::Local);
+                        continue;
+                    }
+                    #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+                    "in" => {
+                        offset = Some(Offset::Zone(parse_zone(tokens)?));
+                        continue;
+                    }
+                    #[cfg(not(any(feature = "tz", feature = "chrono-tz")))]
+                    "in" => {
                         return Err(
-                            "You've already specified the time, you can't specify it again"
+                            "You need to enable the `tz` feature, or the `chrono-tz` feature with the `chrono-backend`, \
+                            to use the time zone names"
                                 .to_string(),
-                        );
+                        )
+                    }
+                    _ => {}
+                }
+                #[cfg(feature = "and-time")]
+                let parsing_date = time.is_none();
+                #[cfg(not(feature = "and-time"))]
+                let parsing_date = true;
+                if parsing_date && date_word(&ident.to_string(), &mut un_calc, &mut period)? {
+                    if let Some(Period::Week { weekday: None, .. }) = period {
+                        next_part = Some(1);
                     }
-                    parsing_date = false;
-                    time_stamp = un_calc.calc()?;
                     continue;
                 }
+                #[cfg(feature = "and-time")]
+                if ident == "at" {
+                    if day_first.is_some() {
+                        return Err("Expected the year after the month".to_string());
+                    }
+                    time = Some(start_time(time, &un_calc)?);
+                    continue;
+                }
+                #[cfg(feature = "and-time")]
+                if let Some(time) = time.as_mut() {
+                    if time.word(&ident.to_string())? {
+                        continue;
+                    }
+                }
                 return Err(format!(
                     "You can't use `{}` here, maybe you meant to enable the `and-time` feature",
                     ident
@@ -116,13 +334,225 @@ fn _parse<C: UnixTimeCalc>(tokens: &mut Peekable<IntoIter>, mut un_calc: C) -> R
             }
         }
     }
-    if time_stamp == 0 {
-        time_stamp = un_calc.calc()?;
-    }
-    if time_stamp == 0 {
+    if !un_calc.is_year_set() || day_first.is_some() {
         return Err("You must at least specify the year".to_string());
     }
-    Ok(time_stamp)
+    #[allow(unused_mut)]
+    let mut time_stamp = match period {
+        Some(Period::Week { week, weekday }) => un_calc.iso_week(week, weekday.unwrap_or(1))?,
+        _ => un_calc.calc()?,
+    };
+    #[cfg(feature = "and-time")]
+    if let Some(time) = time {
+        time_stamp += time.secs()? as i64;
+    }
+    C::to_utc(time_stamp, offset.unwrap_or(Offset::Fixed(0)))
+}
+
+/// Takes a month name, e.g. `March` or `Mar`, or the quarter or the week after the year, e.g. `Q3` or `W12`.
+///
+/// It returns `false` if the word isn't any of them.
+fn date_word<C: UnixTimeCalc>(
+    word: &str,
+    un_calc: &mut C,
+    period: &mut Option<Period>,
+) -> Result<bool> {
+    if let Some(month) = month_number(word) {
+        if un_calc.is_month_set() || period.is_some() {
+            return Err(format!(
+                "Unexpected `{}`, you've already specified the month",
+                word
+            ));
+        }
+        un_calc.month(month.to_string())?;
+        return Ok(true);
+    }
+    let (Some(kind), Some(n)) = (word.get(..1), word.get(1..)) else {
+        return Ok(false);
+    };
+    let (name, example) = match kind {
+        "Q" | "q" => ("quarter", "2025-Q3"),
+        "W" | "w" => ("week", "2025-W12"),
+        _ => return Ok(false),
+    };
+    if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(false);
+    }
+    if !un_calc.is_year_set() || un_calc.is_month_set() || period.is_some() {
+        return Err(format!(
+            "Unexpected `{}`, the {} must follow the year, e.g. `{}`",
+            word, name, example
+        ));
+    }
+    let n = n
+        .parse::<u32>()
+        .map_err(|e| format!("Invalid {} `{}`: {}", name, word, e))?;
+    if name == "quarter" {
+        if !(1..=4).contains(&n) {
+            return Err(format!(
+                "Invalid quarter `{}`, it must be between Q1 and Q4",
+                word
+            ));
+        }
+        un_calc.month(((n - 1) * 3 + 1).to_string())?;
+        *period = Some(Period::Quarter);
+    } else {
+        *period = Some(Period::Week {
+            week: n,
+            weekday: None,
+        });
+    }
+    Ok(true)
+}
+
+/// Starts the time of the day after `at` or `@`
+#[cfg(feature = "and-time")]
+fn start_time<C: UnixTimeCalc>(time: Option<TimeOfDay>, un_calc: &C) -> Result<TimeOfDay> {
+    if time.is_some() {
+        return Err("You've already specified the time, you can't specify it again".to_string());
+    }
+    if !un_calc.is_year_set() {
+        return Err(
+            "You must at least specify year in the date part before specifying the time :)"
+                .to_string(),
+        );
+    }
+    Ok(TimeOfDay::default())
+}
+
+fn _parse_str<C: UnixTimeCalc>(s: &str, mut un_calc: C) -> Result<u64> {
+    let date = Rfc3339::parse(s)?;
+    // The backend checks the date against the calendar and calculates it, like the raw dates
+    un_calc.year(date.year.to_string())?;
+    if let Some(month) = date.month {
+        un_calc.month(month.to_string())?;
+    }
+    if let Some(day) = date.day {
+        un_calc.day(day.to_string())?;
+    }
+    let time_stamp = un_calc.calc()? + i64::from(date.secs);
+    C::to_utc(time_stamp, Offset::Fixed(date.offset))
+}
+
+/// Checks if the `-` before the day is part of the date, e.g. `2025-03`, or the sign of an offset, e.g. `2025-03 -05`.
+///
+/// It's an offset if it's `-HH:MM` or `-HHMM`, or if there's a space before it, but the spaces are only known from rustc 1.88.
+/// A `-` that can't continue the date without a space before it is an error, so the date never changes silently.
+fn is_date_dash(
+    before: Option<Span>,
+    dash: Span,
+    digits: Option<usize>,
+    tokens: &Peekable<IntoIter>,
+) -> Result<bool> {
+    let mut ahead = tokens.clone();
+    // `-05:00` and `-0500` can't be a part of the date, so they don't depend on the spaces
+    if let Some(TokenTree::Literal(lit)) = ahead.next() {
+        let colon = matches!(ahead.next(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':');
+        if colon || lit.to_string().len() > 2 {
+            return Ok(false);
+        }
+    }
+    if before.map_or(false, |before| is_spaced(before, dash)) {
+        return Ok(false);
+    }
+    match (digits, tokens.clone().next()) {
+        (Some(_), Some(TokenTree::Ident(_))) => Ok(true),
+        (Some(digits), Some(TokenTree::Literal(lit)))
+            if lit.to_string().len() <= digits && !is_spaced(dash, lit.span()) =>
+        {
+            Ok(true)
+        }
+        (_, Some(nt)) => Err(format!(
+            "Unexpected `-{}`, put a space before the offset, e.g. `2025-03 -05:00`",
+            nt
+        )),
+        (_, None) => Err("Unexpected `-` at the end of the deadline".to_string()),
+    }
+}
+
+/// Checks if there's a space between the tokens, the spans without a location are all at the start of the line
+fn is_spaced(before: Span, after: Span) -> bool {
+    let (end, start) = (before.end(), after.start());
+    (end.line, end.column) < (start.line, start.column)
+}
+
+fn is_separator(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == ',' || punct.as_char() == ';')
+}
+
+/// Parses the offset after its sign, in the `HH`, `HHMM` or `HH:MM` formats
+fn parse_offset(sign: char, tokens: &mut Peekable<IntoIter>) -> Result<Offset> {
+    let Some(TokenTree::Literal(lit)) = tokens.next() else {
+        return Err(format!("Expected the offset hours after `{}`", sign));
+    };
+    let lit = lit.to_string();
+    let n = lit
+        .parse::<u32>()
+        .map_err(|e| format!("Invalid offset `{}{}`: {}", sign, lit, e))?;
+    let (hours, minutes) = if lit.len() > 2 {
+        (n / 100, n % 100)
+    } else if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':') {
+        let _ = tokens.next();
+        let Some(TokenTree::Literal(minutes)) = tokens.next() else {
+            return Err(format!(
+                "Expected the offset minutes after `{}{}:`",
+                sign, lit
+            ));
+        };
+        let minutes = minutes.to_string();
+        let minutes = minutes
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid offset minutes `{}`: {}", minutes, e))?;
+        (n, minutes)
+    } else {
+        (n, 0)
+    };
+    if hours > 23 {
+        return Err(format!(
+            "Invalid offset hours `{}`, it must be less than 24",
+            hours
+        ));
+    }
+    if minutes > 59 {
+        return Err(format!(
+            "Invalid offset minutes `{}`, it must be less than 60",
+            minutes
+        ));
+    }
+    let offset = (hours * ONE_HOUR as u32 + minutes * 60) as i32;
+    Ok(Offset::Fixed(if sign == '-' { -offset } else { offset }))
+}
+
+/// Collects the time zone name, e.g. `Europe/Berlin` or `America/Port-au-Prince`
+#[cfg(any(feature = "tz", feature = "chrono-tz"))]
+fn parse_zone(tokens: &mut Peekable<IntoIter>) -> Result<String> {
+    let mut zone = String::new();
+    while let Some(nt) = tokens.peek() {
+        if is_separator(nt) {
+            break;
+        }
+        zone.push_str(&nt.to_string());
+        let _ = tokens.next();
+    }
+    if zone.is_empty() {
+        return Err("Expected a time zone name after `in`".to_string());
+    }
+    Ok(zone)
+}
+
+/// Formats a UTC time stamp as RFC 3339, e.g. `2024-10-01T09:00:00Z`
+pub(crate) fn fmt_rfc3339(time_stamp: u64) -> String {
+    let (year, month, day) = civil_from_days((time_stamp / 86_400) as i64);
+    let secs = time_stamp % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
 }
 
 #[cfg(test)]
@@ -139,6 +569,15 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fmt_rfc3339() {
+        assert_eq!(fmt_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(fmt_rfc3339(1_727_740_800), "2024-10-01T00:00:00Z");
+        assert_eq!(fmt_rfc3339(1_709_164_800 + 32_405), "2024-02-29T09:00:05Z");
+        assert_eq!(fmt_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(fmt_rfc3339(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
     #[cfg(feature = "and-time")]
     #[test]
     fn test_parse_date_time() -> Result<()> {
@@ -174,4 +613,543 @@ mod tests {
         assert_eq!(date, 1_727_740_800 + 9 * ONE_HOUR + 2 * 60);
         Ok(())
     }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_date_time_with_seconds_and_meridiem() -> Result<()> {
+        let mut tokens = quote!(2024-10-01 at 9:00:30).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 9 * ONE_HOUR + 30);
+        let mut tokens = quote!(2025-01-31 at 5:30pm).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_738_344_600);
+        let mut tokens = quote!(2025-01-31@5 pm +01:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_738_339_200);
+        let mut tokens = quote!(2025-01-31 at 12:00:01am Z).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_738_281_601);
+        let mut tokens = quote!(2024-10-01 at 9:00:30Z).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 9 * ONE_HOUR + 30);
+        Ok(())
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_date_time_keywords() -> Result<()> {
+        let mut tokens = quote!(2024-10-01 at noon).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 12 * ONE_HOUR);
+        let mut tokens = quote!(2024-10-01 at midnight, if: true)
+            .into_iter()
+            .peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800);
+        let mut tokens = quote!(2024-10-01 at eod -02:00).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens)?,
+            1_727_740_800 + 86_399 + 2 * ONE_HOUR
+        );
+        let mut tokens = quote!(2024-10-01 noon).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        let mut tokens = quote!(2024-10-01 at noon 9).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        let mut tokens = quote!(2024-10-01 at).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_date_with_offset() -> Result<()> {
+        let mut tokens = quote!(2024-10-01 +02:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 - 2 * ONE_HOUR);
+        let mut tokens = quote!(2024 - 10 - 01 - 0530).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens)?,
+            1_727_740_800 + 5 * ONE_HOUR + 30 * 60
+        );
+        let mut tokens = quote!(2024-10-01 Z).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800);
+        // the same as `+` after any date, even when the spaces aren't known
+        let mut tokens = quote!(2024-10 -05:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 5 * ONE_HOUR);
+        let mut tokens = quote!(2024-10 +05:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 - 5 * ONE_HOUR);
+        let mut tokens = quote!(2024 - 0500).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_704_067_200 + 5 * ONE_HOUR);
+        let mut tokens = quote!(2024-Q4 -05:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 5 * ONE_HOUR);
+        let mut tokens = quote!(October 2024 -05:30).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens)?,
+            1_727_740_800 + 5 * ONE_HOUR + 30 * 60
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_local_date() {
+        let tokens = quote!(2024-10-01 local, if: true).into_iter().peekable();
+        assert!(is_local_date(&tokens));
+        let tokens = quote!(2024-10-01, if: local).into_iter().peekable();
+        assert!(!is_local_date(&tokens));
+        #[cfg(not(any(feature = "local-tz", feature = "chrono-backend")))]
+        assert_eq!(
+            parse_date(&mut quote!(2024-10-01 local).into_iter().peekable()).unwrap_err(),
+            "You need to enable the `local-tz` feature to use `local`"
+        );
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_date_time_with_offset() -> Result<()> {
+        let mut tokens = quote!(2024-10-01 at 9:00 +02:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 7 * ONE_HOUR);
+        let mut tokens = quote!(2024-10-01 at 9:00 -05).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 14 * ONE_HOUR);
+        let mut tokens = quote!(2024-10-01@9:00 UTC).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_727_740_800 + 9 * ONE_HOUR);
+        Ok(())
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_date_time_with_offset_around_dst() -> Result<()> {
+        // 2024-03-31 is when the central european clocks go forward,
+        // an explicit offset is taken as is, whatever the DST rules say
+        let mut tokens = quote!(2024-03-31 at 2:30 +01:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_711_848_600);
+        let mut tokens = quote!(2024-03-31 at 2:30 +02:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_711_845_000);
+        // 2024-11-03 01:30 happens twice in New York
+        let mut tokens = quote!(2024-11-03 at 1:30 -04:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_730_611_800);
+        let mut tokens = quote!(2024-11-03 at 1:30 -05:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_730_615_400);
+        Ok(())
+    }
+
+    #[cfg(all(any(feature = "tz", feature = "chrono-tz"), feature = "and-time"))]
+    #[test]
+    fn test_parse_date_time_in_zone() -> Result<()> {
+        let mut tokens = quote!(2025-06-01 at 17:00 in Europe/Berlin)
+            .into_iter()
+            .peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_748_790_000);
+        let mut tokens = quote!(2025-06-01 at 17:00 in America/Port-au-Prince, if: true)
+            .into_iter()
+            .peekable();
+        assert_eq!(parse_date(&mut tokens)?, 1_748_811_600);
+        let mut tokens = quote!(2025-03-30 at 2:30 in Europe/Berlin)
+            .into_iter()
+            .peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        Ok(())
+    }
+
+    #[cfg(any(feature = "with-chrono", feature = "with-time"))]
+    #[test]
+    fn test_parse_date_expr() -> Result<()> {
+        let mut tokens = quote!(chrono::Utc.with_ymd_and_hms(2024, 2, 2, 9, 0, 0), if: true)
+            .into_iter()
+            .peekable();
+        assert!(is_date_expr(&mut tokens));
+        let expr = parse_date_expr(&mut tokens)?;
+        assert_eq!(
+            expr.to_string(),
+            quote!(chrono::Utc.with_ymd_and_hms(2024, 2, 2, 9, 0, 0)).to_string()
+        );
+        assert_eq!(tokens.next().unwrap().to_string(), "if");
+
+        let mut tokens = quote!(2024 - 10 - 01).into_iter().peekable();
+        assert!(!is_date_expr(&mut tokens));
+        let mut tokens = quote!(March 2025).into_iter().peekable();
+        assert!(!is_date_expr(&mut tokens));
+        assert_eq!(tokens.next().unwrap().to_string(), "March");
+        let mut tokens = quote!(march.deadline()).into_iter().peekable();
+        assert!(is_date_expr(&mut tokens));
+        Ok(())
+    }
+
+    #[cfg(feature = "with-time")]
+    #[test]
+    fn test_eval_datetime_macro() -> Result<()> {
+        let expr = quote!(time::macros::datetime!(2024-10-01 09:00 UTC));
+        assert_eq!(
+            eval_datetime_macro(&expr)?,
+            Some(1_727_740_800 + 9 * ONE_HOUR)
+        );
+        let expr = quote!(::time::macros::datetime!(2024-10-01 09:00 +2));
+        assert_eq!(
+            eval_datetime_macro(&expr)?,
+            Some(1_727_740_800 + 7 * ONE_HOUR)
+        );
+        let expr = quote!(datetime!(2024-10-01 9:30));
+        assert_eq!(
+            eval_datetime_macro(&expr)?,
+            Some(1_727_740_800 + 9 * ONE_HOUR + 30 * 60)
+        );
+        let expr = quote!(time::macros::datetime!(2024-10-01 09:00:30 UTC));
+        assert_eq!(
+            eval_datetime_macro(&expr)?,
+            Some(1_727_740_800 + 9 * ONE_HOUR + 30)
+        );
+        // the Tuesday of the 40th week of 2024
+        let expr = quote!(datetime!(2024-W 40-2 9 pm -05:00));
+        assert_eq!(
+            eval_datetime_macro(&expr)?,
+            Some(1_727_740_800 + 26 * ONE_HOUR)
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "with-time")]
+    #[test]
+    fn test_eval_datetime_macro_falls_back_to_runtime() -> Result<()> {
+        let expr = quote!(time::OffsetDateTime::now_utc());
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        let expr = quote!(deadline);
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        let expr = quote!(time::macros::date!(2024 - 10 - 01));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        let expr = quote!(time::macros::datetime!(2024-10-01 09:00:30.5 UTC));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        let expr = quote!(time::macros::datetime!(2024-10-01 09:00 UTC).replace_hour(10));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        // `time` reads it as the 100th day of the year
+        let expr = quote!(time::macros::datetime!(2024-100 09:00 UTC));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        // the year is checked by `time`, it depends on its `large-dates` feature
+        let expr = quote!(time::macros::datetime!(+10000-01-01 09:00 UTC));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        let expr = quote!(time::macros::datetime!(2024-10-01 09:00 +01:00:30));
+        assert_eq!(eval_datetime_macro(&expr)?, None);
+        Ok(())
+    }
+
+    #[cfg(feature = "with-time")]
+    #[test]
+    fn test_eval_datetime_macro_rejects_like_time() {
+        let rejected = |expr: TokenStream| eval_datetime_macro(&expr).unwrap_err();
+        assert_eq!(
+            rejected(quote!(datetime!(2024 - 02 - 02))),
+            "Expected the time after the date, e.g. `datetime!(2024-10-01 09:00 UTC)`"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-02-02 UTC))),
+            "Expected the time after the date, e.g. `datetime!(2024-10-01 09:00 UTC)`"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-02-02 9))),
+            "Expected the minutes or `am`/`pm` after the hour `9`, e.g. `09:00` or `9 am`"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-02-02 5pm))),
+            "Expected the minutes or `am`/`pm` after the hour `5pm`, e.g. `09:00` or `9 am`"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-02-02 9:00 local))),
+            "Unexpected `local` in `datetime!`"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-02-30 09:00))),
+            "Invalid day `30`, February 2024 has 29 days"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2025-W 53-1 09:00))),
+            "Invalid week `W53`, 2025 has 52 weeks"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-10-01 24:00))),
+            "Invalid hour `24`, it must be less than 24"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-10-01 0 pm))),
+            "Invalid hour `0` with `pm`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-10-01 09:00 +26))),
+            "Invalid offset hours `26`, it must be less than 24"
+        );
+        assert_eq!(
+            rejected(quote!(datetime!(2024-10-01 09:00 UTC 1))),
+            "Unexpected `1`, the offset must be the last part of the deadline"
+        );
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_date_before_1970() -> Result<()> {
+        let mut tokens = quote!(1969-12-31 at 23:00 -02:00).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, ONE_HOUR);
+        let mut tokens = quote!(1970 - 01 - 01).into_iter().peekable();
+        assert_eq!(parse_date(&mut tokens)?, 0);
+        let mut tokens = quote!(1969 - 12 - 31).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_date() {
+        let mut tokens = quote!(2023 - 02 - 29).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid day `29`, February 2023 has 28 days"
+        );
+        let mut tokens = quote!(2024 - 13).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid month `13`, it must be between 1 and 12"
+        );
+        let mut tokens = quote!(2024 - 02 - 29).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_ok());
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_invalid_time() {
+        let mut tokens = quote!(2024-10-01 at 24:00).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid hour `24`, it must be less than 24"
+        );
+        let mut tokens = quote!(2024-10-01 at 9:60).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid minute `60`, it must be less than 60"
+        );
+        let mut tokens = quote!(2024-10-01 at 9:00:60).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid second `60`, it must be less than 60"
+        );
+        let mut tokens = quote!(2024-10-01 at 13:00pm).into_iter().peekable();
+        assert_eq!(
+            parse_date(&mut tokens).unwrap_err(),
+            "Invalid hour `13` with `pm`, it must be between 1 and 12"
+        );
+    }
+
+    #[test]
+    fn test_parse_date_str() -> Result<()> {
+        assert_eq!(parse_date_str("2024-10-01")?, 1_727_740_800);
+        assert_eq!(parse_date_str("2024-10")?, 1_727_740_800);
+        assert_eq!(parse_date_str("2024")?, 1_704_067_200);
+        assert_eq!(
+            parse_date_str("2024-10-01T09:00:00Z")?,
+            1_727_740_800 + 9 * ONE_HOUR
+        );
+        assert_eq!(parse_date_str("2025-03-26T09:00:00+02:00")?, 1_742_972_400);
+        assert_eq!(
+            parse_date_str("2024-10-01t09:30z")?,
+            1_727_740_800 + 9 * ONE_HOUR + 30 * 60
+        );
+        assert_eq!(
+            parse_date_str("2024-10-01 09:00:30.250-0530")?,
+            1_727_740_800 + 14 * ONE_HOUR + 30 * 60 + 30
+        );
+        assert_eq!(
+            parse_date_str("2024-10-01T09:00-05")?,
+            1_727_740_800 + 14 * ONE_HOUR
+        );
+        assert_eq!(parse_date_str("1969-12-31T23:00:00-02:00")?, ONE_HOUR);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_date_str() {
+        assert_eq!(
+            parse_date_str("2023-02-29").unwrap_err(),
+            "Invalid day `29`, February 2023 has 28 days"
+        );
+        assert_eq!(
+            parse_date_str("2024-1-01").unwrap_err(),
+            "Invalid month in `2024-1-01`, expected 2 digits"
+        );
+        assert_eq!(
+            parse_date_str("2024-10-01T24:00").unwrap_err(),
+            "Invalid hour `24`, it must be less than 24"
+        );
+        assert_eq!(
+            parse_date_str("2024-10-01T09:00:60Z").unwrap_err(),
+            "Invalid second `60`, it must be less than 60"
+        );
+        assert_eq!(
+            parse_date_str("2024-10-01T09:00+2").unwrap_err(),
+            "Invalid offset `+2` in `2024-10-01T09:00+2`, expected `Z`, `+HH:MM` or `-HH:MM`"
+        );
+        assert_eq!(
+            parse_date_str("2024-10T09:00").unwrap_err(),
+            "Invalid date `2024-10T09:00`, expected `YYYY-MM-DD` with an optional time like `YYYY-MM-DDTHH:MM:SSZ`"
+        );
+        assert!(parse_date_str("").is_err());
+        assert!(parse_date_str("tomorrow").is_err());
+        assert!(parse_date_str("2024-10-01-02").is_err());
+        assert!(parse_date_str("2024-10-01T09").is_err());
+        assert!(parse_date_str("2024-10-01T09:00:00:00").is_err());
+        assert!(parse_date_str("1970-01-01T00:00+01:00").is_err());
+        // used to panic when slicing it
+        assert!(parse_date_str("2025-03-26T09:00+0é00").is_err());
+    }
+
+    fn parse_rfc3339(tokens: proc_macro2::TokenStream) -> Result<String> {
+        parse_date(&mut tokens.into_iter().peekable()).map(fmt_rfc3339)
+    }
+
+    #[test]
+    fn test_parse_quarter() -> Result<()> {
+        assert_eq!(parse_rfc3339(quote!(2025 - Q1))?, "2025-01-01T00:00:00Z");
+        assert_eq!(parse_rfc3339(quote!(2025 - Q3))?, "2025-07-01T00:00:00Z");
+        assert_eq!(
+            parse_rfc3339(quote!(2025-Q4 +02:00))?,
+            "2025-09-30T22:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - Q5)).unwrap_err(),
+            "Invalid quarter `Q5`, it must be between Q1 and Q4"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - 03 - Q1)).unwrap_err(),
+            "Unexpected `Q1`, the quarter must follow the year, e.g. `2025-Q3`"
+        );
+        assert!(parse_rfc3339(quote!(2025 - Q3 - 15)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_iso_week() -> Result<()> {
+        assert_eq!(parse_rfc3339(quote!(2025 - W12))?, "2025-03-17T00:00:00Z");
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - W12 - 3))?,
+            "2025-03-19T00:00:00Z"
+        );
+        // the first week has January 4th, so it can start in the previous year
+        assert_eq!(parse_rfc3339(quote!(2025 - W01))?, "2024-12-30T00:00:00Z");
+        assert_eq!(
+            parse_rfc3339(quote!(2021 - W01 - 1))?,
+            "2021-01-04T00:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2027 - W01 - 1))?,
+            "2027-01-04T00:00:00Z"
+        );
+        // and the last week can end in the next year
+        assert_eq!(
+            parse_rfc3339(quote!(2026 - W53 - 1))?,
+            "2026-12-28T00:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2026 - W53 - 7))?,
+            "2027-01-03T00:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2020 - W53 - 5))?,
+            "2021-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2024 - W52 - 7))?,
+            "2024-12-29T00:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - W53)).unwrap_err(),
+            "Invalid week `W53`, 2025 has 52 weeks"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - W00)).unwrap_err(),
+            "Invalid week `W0`, the weeks of the year start from 1"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025 - W12 - 8)).unwrap_err(),
+            "Invalid weekday `8`, it must be between 1 (Monday) and 7 (Sunday)"
+        );
+        assert!(parse_rfc3339(quote!(W12)).is_err());
+        assert!(parse_rfc3339(quote!(2025 - W12 - 3 - 4)).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "and-time")]
+    #[test]
+    fn test_parse_iso_week_at_time() -> Result<()> {
+        assert_eq!(
+            parse_rfc3339(quote!(2025-W12-5 at 5pm -01:00))?,
+            "2025-03-21T18:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(2025-Q2 at noon))?,
+            "2025-04-01T12:00:00Z"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_month_name() -> Result<()> {
+        assert_eq!(parse_rfc3339(quote!(March 2025))?, "2025-03-01T00:00:00Z");
+        assert_eq!(parse_rfc3339(quote!(15 Mar 2025))?, "2025-03-15T00:00:00Z");
+        assert_eq!(parse_rfc3339(quote!(31 dec 2024))?, "2024-12-31T00:00:00Z");
+        assert_eq!(
+            parse_rfc3339(quote!(1 Jan 2025 +01:00))?,
+            "2024-12-31T23:00:00Z"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(29 Sept 2025, if: true))?,
+            "2025-09-29T00:00:00Z"
+        );
+        assert_eq!(parse_rfc3339(quote!(2025 May))?, "2025-05-01T00:00:00Z");
+        assert_eq!(
+            parse_rfc3339(quote!(29 February 2025)).unwrap_err(),
+            "Invalid day `29`, February 2025 has 28 days"
+        );
+        assert_eq!(
+            parse_rfc3339(quote!(March)).unwrap_err(),
+            "You must at least specify the year"
+        );
+        assert!(parse_rfc3339(quote!(15 Mar)).is_err());
+        assert!(parse_rfc3339(quote!(2025-03 March)).is_err());
+        assert!(parse_rfc3339(quote!(Marchy 2025)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_negative_offset_after_every_form() -> Result<()> {
+        // `quote!` doesn't keep the spaces, so the deadlines are parsed like the compiler gives them
+        let parse = |date: &str| {
+            let tokens = date.parse::<proc_macro2::TokenStream>().unwrap();
+            parse_date(&mut tokens.into_iter().peekable())
+        };
+        for date in [
+            "2099",
+            "2099-03",
+            "2099-03-01",
+            "2099-Q2",
+            "2099-W12",
+            "2099-W12-5",
+            "March 2099",
+            "15 Mar 2099",
+        ] {
+            for offset in ["-05", "-05:00"] {
+                let deadline = format!("{} {}", date, offset);
+                assert_eq!(
+                    parse(&deadline)?,
+                    parse(date)? + 5 * ONE_HOUR,
+                    "{}",
+                    deadline
+                );
+            }
+        }
+        assert_eq!(
+            parse("2099-W12-05").unwrap_err(),
+            "Unexpected `-05`, put a space before the offset, e.g. `2025-03 -05:00`"
+        );
+        assert_eq!(
+            parse("2099-Q2-05").unwrap_err(),
+            "Unexpected `-05`, put a space before the offset, e.g. `2025-03 -05:00`"
+        );
+        assert!(parse("March 2099-05").is_err());
+        assert!(parse("2099 -").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_date_with_invalid_offset() {
+        let mut tokens = quote!(2024-10-01 +25:00).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        let mut tokens = quote!(2024-10-01 +02:60).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+        let mut tokens = quote!(2024-10-01 Z 9).into_iter().peekable();
+        assert!(parse_date(&mut tokens).is_err());
+    }
 }
@@ -0,0 +1,170 @@
+use crate::Result;
+
+/// The time of the day in the `by` condition, e.g. `9:30`, `17:45:10`, `5:30pm` or `noon`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct TimeOfDay {
+    /// The hour, the minute and the second, in the order they're written
+    parts: Vec<u32>,
+    meridiem: Option<Meridiem>,
+    /// The time was given with a keyword like `noon`, so it can't have any other part
+    keyword: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Meridiem {
+    Am,
+    Pm,
+}
+
+impl TimeOfDay {
+    /// Takes the next number of the time, `5pm` and `30pm` are tokenized as one literal
+    pub(super) fn number(&mut self, lit: &str) -> Result<()> {
+        let digits = lit.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let suffix = &lit[digits.len()..];
+        if self.keyword || self.meridiem.is_some() || self.parts.len() == 3 {
+            return Err(format!("Unexpected `{}` after the time", lit));
+        }
+        let n = digits
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid number `{}`: {}", lit, e))?;
+        self.parts.push(n);
+        if !suffix.is_empty() && !self.word(suffix)? {
+            return Err(format!("Invalid number `{}`", lit));
+        }
+        Ok(())
+    }
+
+    /// Takes `am`, `pm`, `noon`, `midnight` or `eod`, it returns `false` for any other word
+    pub(super) fn word(&mut self, word: &str) -> Result<bool> {
+        let meridiem = match word.to_ascii_lowercase().as_str() {
+            "am" => Meridiem::Am,
+            "pm" => Meridiem::Pm,
+            "noon" => return self.keyword(word, [12, 0, 0]),
+            "midnight" => return self.keyword(word, [0, 0, 0]),
+            "eod" => return self.keyword(word, [23, 59, 59]),
+            _ => return Ok(false),
+        };
+        if self.parts.is_empty() || self.meridiem.is_some() || self.keyword {
+            return Err(format!("Unexpected `{}`, it must follow the hour", word));
+        }
+        self.meridiem = Some(meridiem);
+        Ok(true)
+    }
+
+    fn keyword(&mut self, word: &str, parts: [u32; 3]) -> Result<bool> {
+        if !self.parts.is_empty() {
+            return Err(format!(
+                "Unexpected `{}`, you've already specified the time",
+                word
+            ));
+        }
+        self.parts = parts.to_vec();
+        self.keyword = true;
+        Ok(true)
+    }
+
+    /// Returns the seconds since the start of the day, after checking the bounds of every part
+    pub(super) fn secs(&self) -> Result<u64> {
+        let &[hour, ref rest @ ..] = self.parts.as_slice() else {
+            return Err("Expected the time after `at`".to_string());
+        };
+        let minute = rest.first().copied().unwrap_or(0);
+        let second = rest.get(1).copied().unwrap_or(0);
+        let hour = match self.meridiem {
+            Some(meridiem) => {
+                if !(1..=12).contains(&hour) {
+                    return Err(format!(
+                        "Invalid hour `{}` with `{}`, it must be between 1 and 12",
+                        hour,
+                        if meridiem == Meridiem::Am { "am" } else { "pm" }
+                    ));
+                }
+                // `12am` is the midnight and `12pm` is the noon
+                hour % 12 + if meridiem == Meridiem::Pm { 12 } else { 0 }
+            }
+            None if hour > 23 => {
+                return Err(format!("Invalid hour `{}`, it must be less than 24", hour))
+            }
+            None => hour,
+        };
+        if minute > 59 {
+            return Err(format!(
+                "Invalid minute `{}`, it must be less than 60",
+                minute
+            ));
+        }
+        if second > 59 {
+            return Err(format!(
+                "Invalid second `{}`, it must be less than 60",
+                second
+            ));
+        }
+        Ok(u64::from(hour * 3600 + minute * 60 + second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(parts: &[&str]) -> Result<u64> {
+        let mut time = TimeOfDay::default();
+        for part in parts {
+            if part.starts_with(|c: char| c.is_ascii_digit()) {
+                time.number(part)?;
+            } else if !time.word(part)? {
+                return Err(format!("Unknown `{}`", part));
+            }
+        }
+        time.secs()
+    }
+
+    #[test]
+    fn test_time_of_day() -> Result<()> {
+        assert_eq!(secs(&["9"])?, 9 * 3600);
+        assert_eq!(secs(&["17", "45"])?, 17 * 3600 + 45 * 60);
+        assert_eq!(secs(&["17", "45", "10"])?, 17 * 3600 + 45 * 60 + 10);
+        assert_eq!(secs(&["5", "30pm"])?, 17 * 3600 + 30 * 60);
+        assert_eq!(secs(&["5pm"])?, 17 * 3600);
+        assert_eq!(secs(&["5", "30", "PM"])?, 17 * 3600 + 30 * 60);
+        assert_eq!(secs(&["12am"])?, 0);
+        assert_eq!(secs(&["12", "15pm"])?, 12 * 3600 + 15 * 60);
+        assert_eq!(secs(&["11", "59", "59pm"])?, 86_399);
+        assert_eq!(secs(&["noon"])?, 12 * 3600);
+        assert_eq!(secs(&["midnight"])?, 0);
+        assert_eq!(secs(&["eod"])?, 86_399);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_time_of_day() {
+        assert_eq!(
+            secs(&["24"]).unwrap_err(),
+            "Invalid hour `24`, it must be less than 24"
+        );
+        assert_eq!(
+            secs(&["13pm"]).unwrap_err(),
+            "Invalid hour `13` with `pm`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            secs(&["0am"]).unwrap_err(),
+            "Invalid hour `0` with `am`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            secs(&["9", "60"]).unwrap_err(),
+            "Invalid minute `60`, it must be less than 60"
+        );
+        assert_eq!(
+            secs(&["9", "30", "60"]).unwrap_err(),
+            "Invalid second `60`, it must be less than 60"
+        );
+        assert!(secs(&["9", "30", "00", "00"]).is_err());
+        assert!(secs(&["9pm", "30"]).is_err());
+        assert!(secs(&["noon", "30"]).is_err());
+        assert!(secs(&["9", "noon"]).is_err());
+        assert!(secs(&["pm"]).is_err());
+        assert!(secs(&["9", "am", "pm"]).is_err());
+        assert!(secs(&["9x"]).is_err());
+        assert!(secs(&[]).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::Result;
-use chrono::NaiveDate;
+use chrono::{DateTime, Local, NaiveDate, Offset, TimeZone};
 
 pub(crate) struct ChronoCalc {
     pub(crate) year: i32,
@@ -9,13 +9,38 @@ pub(crate) struct ChronoCalc {
 
 impl ChronoCalc {
     #[inline(always)]
-    pub(super) fn _calc(&self) -> Result<u64> {
+    pub(super) fn _calc(&self) -> Result<i64> {
         let date =
             NaiveDate::from_ymd_opt(self.year, self.month.unwrap_or(1), self.day.unwrap_or(1))
                 .ok_or_else(|| "Invalid date".to_string())?;
         Ok(date
             .and_hms_opt(0, 0, 0)
             .expect("Internal error")
-            .timestamp() as u64)
+            .and_utc()
+            .timestamp())
+    }
+
+    #[inline(always)]
+    pub(super) fn _local_offset(time_stamp: i64) -> Result<i32> {
+        let utc = DateTime::from_timestamp(time_stamp, 0)
+            .ok_or_else(|| "The deadline is out of range".to_string())?;
+        Ok(Local
+            .offset_from_utc_datetime(&utc.naive_utc())
+            .fix()
+            .local_minus_utc())
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[inline(always)]
+    pub(super) fn _zone_offset(zone: &str, time_stamp: i64) -> Result<i32> {
+        let tz = zone
+            .parse::<chrono_tz::Tz>()
+            .map_err(|_| format!("Unknown time zone `{}`", zone))?;
+        let utc = DateTime::from_timestamp(time_stamp, 0)
+            .ok_or_else(|| "The deadline is out of range".to_string())?;
+        Ok(tz
+            .offset_from_utc_datetime(&utc.naive_utc())
+            .fix()
+            .local_minus_utc())
     }
 }
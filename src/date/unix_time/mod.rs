@@ -6,21 +6,250 @@ pub(super) mod simple;
 pub(super) mod time;
 
 use crate::Result;
+use todo2_runtime::date::days_in_month;
+pub(super) use todo2_runtime::date::ONE_DAY;
 
 pub(super) const ONE_HOUR: u64 = 3600;
 
-pub(super) trait UnixTimeCalc {
-    fn new() -> Self;
-    fn is_leap_year(year: u32) -> bool {
-        year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+/// The UTC offset that the deadline is written in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Offset {
+    /// A fixed offset in seconds east of UTC
+    Fixed(i32),
+    /// The time zone of the build machine
+    Local,
+    /// An IANA time zone name, e.g. `Europe/Berlin`
+    #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+    Zone(String),
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Returns the number of the month from its English name or its first three letters, e.g. `March` or `mar`
+pub(super) fn month_number(name: &str) -> Option<u32> {
+    let name = name.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| {
+            let month = month.to_ascii_lowercase();
+            name == month || name == month[..3] || (name == "sept" && month == "september")
+        })
+        .map(|i| i as u32 + 1)
+}
+
+fn parse_year(year: &str) -> Result<i32> {
+    match year.parse::<i32>() {
+        Ok(n) if (1..=9999).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "Invalid year `{}`, it must be between 1 and 9999",
+            year
+        )),
+    }
+}
+
+fn parse_month(month: &str) -> Result<u32> {
+    match month.parse::<u32>() {
+        Ok(n) if (1..=12).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "Invalid month `{}`, it must be between 1 and 12",
+            month
+        )),
+    }
+}
+
+/// Checks the day against the month, so every backend reports the same errors, e.g. `February 2023 has 28 days`
+fn parse_day(year: i32, month: u32, day: &str) -> Result<u32> {
+    let days = days_in_month(year, month);
+    match day.parse::<u32>() {
+        Ok(n) if (1..=days).contains(&n) => Ok(n),
+        Ok(0) => Err("Invalid day `0`, the days of the month start from 1".to_string()),
+        Ok(_) => Err(format!(
+            "Invalid day `{}`, {} {} has {} days",
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            days
+        )),
+        Err(_) => Err(format!("Invalid day `{}`", day)),
     }
+}
+
+/// The backends get the date components after they're checked against the calendar
+pub(super) trait UnixTimeCalc: Sized {
+    fn new() -> Self;
     fn year(&mut self, year: String) -> Result<()>;
     fn month(&mut self, month: String) -> Result<()>;
     fn day(&mut self, day: String) -> Result<()>;
+    fn year_number(&self) -> i32;
     fn is_year_set(&self) -> bool;
     fn is_month_set(&self) -> bool;
     fn is_day_set(&self) -> bool;
-    fn calc(&self) -> Result<u64>;
+    /// Returns the unix time stamp of the date at midnight, it's negative before 1970
+    fn calc(&self) -> Result<i64>;
+    /// Returns the unix time stamp of the day in the ISO week of the year, e.g. `2025-W12-3`,
+    /// the weeks start on Monday and the first one is the week with January 4th
+    fn iso_week(&self, week: u32, weekday: u32) -> Result<i64> {
+        let first_monday = |year: i32| -> Result<i64> {
+            let mut calc = Self::new();
+            calc.year(year.to_string())?;
+            calc.month("1".to_string())?;
+            calc.day("4".to_string())?;
+            let days = calc.calc()?.div_euclid(ONE_DAY);
+            // 1970-01-01 was a Thursday
+            Ok(days - (days + 3).rem_euclid(7))
+        };
+        let year = self.year_number();
+        let first = first_monday(year)?;
+        let weeks = (first_monday(year + 1)? - first) / 7;
+        if week == 0 {
+            return Err("Invalid week `W0`, the weeks of the year start from 1".to_string());
+        }
+        if i64::from(week) > weeks {
+            return Err(format!(
+                "Invalid week `W{}`, {} has {} weeks",
+                week, year, weeks
+            ));
+        }
+        if !(1..=7).contains(&weekday) {
+            return Err(format!(
+                "Invalid weekday `{}`, it must be between 1 (Monday) and 7 (Sunday)",
+                weekday
+            ));
+        }
+        Ok((first + i64::from((week - 1) * 7 + weekday - 1)) * ONE_DAY)
+    }
+    /// Returns the offset of the build machine time zone in seconds at the given unix time stamp
+    fn local_offset(time_stamp: i64) -> Result<i32>;
+    /// Returns the offset of the IANA time zone in seconds at the given unix time stamp
+    #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+    fn zone_offset(zone: &str, time_stamp: i64) -> Result<i32>;
+    /// Converts a wall clock time stamp that is written in the given offset to UTC
+    fn to_utc(time_stamp: i64, offset: Offset) -> Result<u64> {
+        let time_stamp = match offset {
+            Offset::Fixed(offset) => time_stamp - offset as i64,
+            Offset::Local => wall_to_utc(time_stamp, Self::local_offset)?,
+            #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+            Offset::Zone(name) => {
+                zone_to_utc(time_stamp, &name, |ts| Self::zone_offset(&name, ts))?
+            }
+        };
+        u64::try_from(time_stamp)
+            .map_err(|_| "The deadline can't be before the unix epoch".to_string())
+    }
+}
+
+/// Resolves a wall clock time stamp to UTC, using `offset_at` to get the offset at a UTC instant.
+///
+/// The first lookup treats the wall clock as UTC to guess the offset, and the second one corrects it.
+/// A wall clock time that falls in a DST gap gets shifted forward,
+/// and an ambiguous one (the repeated hour when the clocks go back) resolves to the later instant.
+pub(super) fn wall_to_utc<F: Fn(i64) -> Result<i32>>(time_stamp: i64, offset_at: F) -> Result<i64> {
+    let guess = offset_at(time_stamp)?;
+    Ok(time_stamp - offset_at(time_stamp - guess as i64)? as i64)
+}
+
+/// Resolves a wall clock time stamp in a named time zone to UTC.
+///
+/// Unlike [`wall_to_utc`], a wall clock time that doesn't exist or happens twice
+/// because of a DST transition is an error that lists the possible instants.
+#[cfg(any(feature = "tz", feature = "chrono-tz"))]
+pub(super) fn zone_to_utc<F: Fn(i64) -> Result<i32>>(
+    time_stamp: i64,
+    zone: &str,
+    offset_at: F,
+) -> Result<i64> {
+    let before = offset_at(time_stamp - ONE_DAY)?;
+    let after = offset_at(time_stamp + ONE_DAY)?;
+    let mut instants = Vec::with_capacity(2);
+    for offset in [before, after] {
+        let instant = time_stamp - offset as i64;
+        if offset_at(instant)? == offset && !instants.contains(&instant) {
+            instants.push(instant);
+        }
+    }
+    let wall = fmt_time_of_day(time_stamp);
+    match instants.as_slice() {
+        [instant] => Ok(*instant),
+        [] => Err(format!(
+            "The time {} doesn't exist in `{}` because the clocks go forward from {} to {}, \
+            it can be either {} {} or {} {}",
+            wall,
+            zone,
+            fmt_offset(before),
+            fmt_offset(after),
+            fmt_time_of_day(time_stamp - before as i64 + after as i64),
+            fmt_offset(after),
+            fmt_time_of_day(time_stamp - after as i64 + before as i64),
+            fmt_offset(before),
+        )),
+        _ => Err(format!(
+            "The time {} is ambiguous in `{}` because the clocks go back from {} to {}, \
+            it can be either {} {} or {} {}, use one of these offsets instead of the zone name",
+            wall,
+            zone,
+            fmt_offset(before),
+            fmt_offset(after),
+            wall,
+            fmt_offset(before),
+            wall,
+            fmt_offset(after),
+        )),
+    }
+}
+
+#[cfg(any(feature = "tz", feature = "chrono-tz"))]
+fn fmt_time_of_day(time_stamp: i64) -> String {
+    let secs = time_stamp.rem_euclid(86_400);
+    format!("{:02}:{:02}", secs / 3600, secs / 60 % 60)
+}
+
+#[cfg(any(feature = "tz", feature = "chrono-tz"))]
+fn fmt_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!("{}{:02}:{:02}", sign, offset / 3600, offset / 60 % 60)
+}
+
+/// Looks up the build machine time zone through the `TZ` environment variable or `/etc/localtime`
+#[cfg(all(feature = "local-tz", not(feature = "chrono-backend")))]
+pub(super) fn system_local_offset(time_stamp: i64) -> Result<i32> {
+    let tz = match std::env::var("TZ") {
+        Ok(tz) if !tz.is_empty() => ::tz::TimeZone::from_posix_tz(&tz),
+        _ => ::tz::TimeZone::local(),
+    }
+    .map_err(|e| format!("Can't find the local time zone of the build machine: {}", e))?;
+    let local_time_type = tz
+        .find_local_time_type(time_stamp)
+        .map_err(|e| format!("Can't find the local time zone offset: {}", e))?;
+    Ok(local_time_type.ut_offset())
+}
+
+#[cfg(not(any(feature = "local-tz", feature = "chrono-backend")))]
+pub(super) fn system_local_offset(_time_stamp: i64) -> Result<i32> {
+    Err("You need to enable the `local-tz` feature to use `local`".to_string())
+}
+
+/// Looks up the IANA time zone in the database that `tzdb` embeds
+#[cfg(all(feature = "tz", not(feature = "chrono-backend")))]
+pub(super) fn tzdb_offset(zone: &str, time_stamp: i64) -> Result<i32> {
+    let tz = ::tzdb::tz_by_name(zone).ok_or_else(|| format!("Unknown time zone `{}`", zone))?;
+    Ok(tz
+        .find_local_time_type(time_stamp)
+        .map_err(|e| format!("Can't find the offset of `{}`: {}", zone, e))?
+        .ut_offset())
 }
 
 macro_rules! impl_unixtime_calc {
@@ -35,20 +264,26 @@ macro_rules! impl_unixtime_calc {
             }
 
             fn year(&mut self, year: String) -> Result<()> {
-                self.year = year.parse::<i32>().map_err(|_| "Invalid year")?;
+                self.year = parse_year(&year)?;
                 Ok(())
             }
 
             fn month(&mut self, month: String) -> Result<()> {
-                self.month = Some(month.parse::<$mt>().map_err(|_| "Invalid month")?);
+                // It's between 1 and 12, so it fits in any integer type
+                self.month = Some(parse_month(&month)? as $mt);
                 Ok(())
             }
 
             fn day(&mut self, day: String) -> Result<()> {
-                self.day = Some(day.parse::<$dt>().map_err(|_| "Invalid day")?);
+                let month = self.month.map_or(1, u32::from);
+                self.day = Some(parse_day(self.year, month, &day)? as $dt);
                 Ok(())
             }
 
+            fn year_number(&self) -> i32 {
+                self.year
+            }
+
             fn is_year_set(&self) -> bool {
                 self.year != 0
             }
@@ -61,9 +296,18 @@ macro_rules! impl_unixtime_calc {
                 self.day.is_some()
             }
 
-            fn calc(&self) -> Result<u64> {
+            fn calc(&self) -> Result<i64> {
                 self._calc()
             }
+
+            fn local_offset(time_stamp: i64) -> Result<i32> {
+                Self::_local_offset(time_stamp)
+            }
+
+            #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+            fn zone_offset(zone: &str, time_stamp: i64) -> Result<i32> {
+                Self::_zone_offset(zone, time_stamp)
+            }
         }
     };
 }
@@ -72,27 +316,39 @@ macro_rules! impl_unixtime_calc {
 impl_unixtime_calc!(chrono::ChronoCalc, u32, u32);
 #[cfg(feature = "time-backend")]
 impl_unixtime_calc!(time::TimeCalc, u8, u8);
+#[cfg(not(any(feature = "chrono-backend", feature = "time-backend")))]
+impl_unixtime_calc!(simple::SimpleCalc, u32, u32);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const YYY_MM_DD_INPUTS: [((&str, &str, &str), u64); 3] = [
+    #[cfg(feature = "chrono-backend")]
+    type Calc = chrono::ChronoCalc;
+    #[cfg(feature = "time-backend")]
+    type Calc = time::TimeCalc;
+    #[cfg(not(any(feature = "chrono-backend", feature = "time-backend")))]
+    type Calc = simple::SimpleCalc;
+
+    const YYY_MM_DD_INPUTS: [((&str, &str, &str), i64); 5] = [
         (("2003", "2", "2"), 1_044_144_000),
         (("2001", "2", "28"), 983_318_400),
         (("2033", "3", "26"), 1_995_408_000),
+        (("1969", "12", "31"), -86_400),
+        (("1900", "3", "1"), -2_203_891_200),
     ];
 
-    const YYY_MM_INPUTS: [((&str, &str), u64); 3] = [
+    const YYY_MM_INPUTS: [((&str, &str), i64); 3] = [
         (("2003", "2"), 1_044_057_600),
         (("2001", "2"), 980_985_600),
         (("2033", "3"), 1_993_248_000),
     ];
 
-    const YYY_INPUTS: [(&str, u64); 3] = [
+    const YYY_INPUTS: [(&str, i64); 4] = [
         ("2003", 1_041_379_200),
         ("2001", 978_307_200),
         ("2033", 1_988_150_400),
+        ("1600", -11_676_096_000),
     ];
 
     fn test_calc<C: UnixTimeCalc>() -> Result<()> {
@@ -120,9 +376,184 @@ mod tests {
         Ok(())
     }
 
+    /// Compares every day from 1600 to 2400 with the `chrono` and the `time` crates, they're in the dev dependencies
+    fn cross_check<C: UnixTimeCalc>() -> Result<()> {
+        let mut date = ::time::Date::from_calendar_date(1600, ::time::Month::January, 1).unwrap();
+        while date.year() < 2400 {
+            let (year, month, day) = date.to_calendar_date();
+            let mut calc = C::new();
+            calc.year(year.to_string())?;
+            calc.month((month as u8).to_string())?;
+            calc.day(day.to_string())?;
+            let time_stamp = calc.calc()?;
+            assert_eq!(
+                time_stamp,
+                date.midnight().assume_utc().unix_timestamp(),
+                "{}",
+                date
+            );
+            let chrono_date =
+                ::chrono::NaiveDate::from_ymd_opt(year, month as u32, u32::from(day)).unwrap();
+            assert_eq!(
+                time_stamp,
+                chrono_date
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp(),
+                "{}",
+                date
+            );
+            date = date.next_day().unwrap();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_check_backends() -> Result<()> {
+        cross_check::<Calc>()
+    }
+
+    fn calc_err(year: &str, month: &str, day: &str) -> String {
+        let mut calc = Calc::new();
+        calc.year(year.to_string())
+            .and_then(|_| calc.month(month.to_string()))
+            .and_then(|_| calc.day(day.to_string()))
+            .and_then(|_| calc.calc())
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_calendar_validation() {
+        assert_eq!(
+            calc_err("2023", "2", "29"),
+            "Invalid day `29`, February 2023 has 28 days"
+        );
+        assert_eq!(
+            calc_err("1900", "2", "29"),
+            "Invalid day `29`, February 1900 has 28 days"
+        );
+        assert_eq!(
+            calc_err("2024", "2", "30"),
+            "Invalid day `30`, February 2024 has 29 days"
+        );
+        assert_eq!(
+            calc_err("2024", "4", "31"),
+            "Invalid day `31`, April 2024 has 30 days"
+        );
+        assert_eq!(
+            calc_err("2024", "1", "0"),
+            "Invalid day `0`, the days of the month start from 1"
+        );
+        assert_eq!(
+            calc_err("2024", "13", "1"),
+            "Invalid month `13`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            calc_err("2024", "300", "1"),
+            "Invalid month `300`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            calc_err("2024", "0", "1"),
+            "Invalid month `0`, it must be between 1 and 12"
+        );
+        assert_eq!(
+            calc_err("10000", "1", "1"),
+            "Invalid year `10000`, it must be between 1 and 9999"
+        );
+    }
+
+    #[test]
+    fn test_calc_before_1970() -> Result<()> {
+        // used to underflow in the simple backend
+        let mut calc = Calc::new();
+        calc.year("1969".to_string())?;
+        assert_eq!(calc.calc()?, -365 * 86_400);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wall_to_utc_fixed() -> Result<()> {
+        let tz = ::tz::TimeZone::fixed(-5 * 3600).unwrap();
+        let offset_at = |ts| Ok(tz.find_local_time_type(ts).unwrap().ut_offset());
+        // 2024-03-10 09:00 in UTC-5
+        assert_eq!(wall_to_utc(1_710_061_200, offset_at)?, 1_710_079_200);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wall_to_utc_dst_edges() -> Result<()> {
+        // Central European Time, the clocks go forward on the last sunday of march at 02:00
+        // and back on the last sunday of october at 03:00
+        let tz = ::tz::TimeZone::from_posix_tz("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let offset_at = |ts| Ok(tz.find_local_time_type(ts).unwrap().ut_offset());
+
+        // 2024-03-31 01:59, the last minute before the gap, still CET
+        assert_eq!(wall_to_utc(1_711_850_340, offset_at)?, 1_711_846_740);
+        // 2024-03-31 02:30 doesn't exist, shifted forward to 03:30 CEST
+        assert_eq!(wall_to_utc(1_711_852_200, offset_at)?, 1_711_848_600);
+        // 2024-03-31 03:00, the first minute after the gap, CEST
+        assert_eq!(wall_to_utc(1_711_854_000, offset_at)?, 1_711_846_800);
+        // 2024-10-27 01:30, before the overlap, CEST
+        assert_eq!(wall_to_utc(1_729_992_600, offset_at)?, 1_729_985_400);
+        // 2024-10-27 02:30 happens twice, resolves to the later one in CET
+        assert_eq!(wall_to_utc(1_729_996_200, offset_at)?, 1_729_992_600);
+        // 2024-10-27 03:00, after the overlap, CET
+        assert_eq!(wall_to_utc(1_729_998_000, offset_at)?, 1_729_994_400);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+    #[test]
+    fn test_zone_to_utc_dst_edges() -> Result<()> {
+        let berlin = Offset::Zone("Europe/Berlin".to_string());
+        // 2025-06-01 17:00 CEST
+        assert_eq!(Calc::to_utc(1_748_797_200, berlin.clone())?, 1_748_790_000);
+        // 2025-01-15 17:00 CET
+        assert_eq!(Calc::to_utc(1_736_960_400, berlin.clone())?, 1_736_956_800);
+        // 2025-03-30 03:00 CEST, right after the gap
+        assert_eq!(Calc::to_utc(1_743_303_600, berlin.clone())?, 1_743_296_400);
+
+        // 2025-03-30 02:30 doesn't exist
+        let err = Calc::to_utc(1_743_301_800, berlin.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            "The time 02:30 doesn't exist in `Europe/Berlin` because the clocks go forward from \
+            +01:00 to +02:00, it can be either 03:30 +02:00 or 01:30 +01:00"
+        );
+        // 2025-10-26 02:30 happens twice
+        let err = Calc::to_utc(1_761_445_800, berlin).unwrap_err();
+        assert_eq!(
+            err,
+            "The time 02:30 is ambiguous in `Europe/Berlin` because the clocks go back from \
+            +02:00 to +01:00, it can be either 02:30 +02:00 or 02:30 +01:00, \
+            use one of these offsets instead of the zone name"
+        );
+        Ok(())
+    }
+
+    #[cfg(any(feature = "tz", feature = "chrono-tz"))]
+    #[test]
+    fn test_unknown_zone() {
+        assert!(Calc::to_utc(1_748_797_200, Offset::Zone("Mars/Olympus".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_to_utc_fixed_offset() -> Result<()> {
+        assert_eq!(
+            Calc::to_utc(1_727_740_800, Offset::Fixed(2 * ONE_HOUR as i32))?,
+            1_727_740_800 - 2 * ONE_HOUR
+        );
+        assert_eq!(
+            Calc::to_utc(1_727_740_800, Offset::Fixed(-(5 * ONE_HOUR as i32)))?,
+            1_727_740_800 + 5 * ONE_HOUR
+        );
+        assert!(Calc::to_utc(0, Offset::Fixed(ONE_HOUR as i32)).is_err());
+        Ok(())
+    }
+
     #[cfg(not(any(feature = "chrono-backend", feature = "time-backend")))]
     #[test]
-    #[ignore] // We know that the simple backend is not accurate right now
     fn test_calc_simple() -> Result<()> {
         test_calc::<simple::SimpleCalc>()
     }
@@ -1,55 +1,30 @@
-use crate::date::unix_time::UnixTimeCalc;
+use crate::date::unix_time::ONE_DAY;
 use crate::Result;
+use todo2_runtime::date::days_from_civil;
 
-const EIGHT_HOURS: u64 = 28_798;
-const ONE_DAY: u64 = 86_400;
-const ONE_MONTH: u64 = 2_629_743;
-const ONE_YEAR: u64 = 31_556_926;
-
+/// Calculates the unix time stamp without any dependency, with the proleptic gregorian calendar
 pub(crate) struct SimpleCalc {
-    counter: u8,
-    time_stamp: u64,
+    pub(super) year: i32,
+    pub(super) month: Option<u32>,
+    pub(super) day: Option<u32>,
 }
 
-impl UnixTimeCalc for SimpleCalc {
-    fn new() -> Self {
-        Self {
-            counter: 0,
-            time_stamp: 0,
-        }
-    }
-
-    fn year(&mut self, year: String) -> Result<()> {
-        self.time_stamp += (year.parse::<u64>().map_err(|_| "Invalid year")? - 1970) * ONE_YEAR;
-        self.counter += 1;
-        Ok(())
-    }
-
-    fn month(&mut self, month: String) -> Result<()> {
-        self.time_stamp += month.parse::<u64>().map_err(|_| "Invalid month")? * ONE_MONTH;
-        self.counter += 1;
-        Ok(())
-    }
-
-    fn day(&mut self, day: String) -> Result<()> {
-        self.time_stamp += day.parse::<u64>().map_err(|_| "Invalid day")? * ONE_DAY;
-        self.counter += 1;
-        Ok(())
-    }
-
-    fn is_year_set(&self) -> bool {
-        self.counter >= 1
-    }
-
-    fn is_month_set(&self) -> bool {
-        self.counter >= 2
+impl SimpleCalc {
+    #[inline(always)]
+    pub(super) fn _calc(&self) -> Result<i64> {
+        let month = self.month.unwrap_or(1);
+        let day = self.day.unwrap_or(1);
+        Ok(days_from_civil(self.year, month, day) * ONE_DAY)
     }
 
-    fn is_day_set(&self) -> bool {
-        self.counter >= 3
+    #[inline(always)]
+    pub(super) fn _local_offset(time_stamp: i64) -> Result<i32> {
+        super::system_local_offset(time_stamp)
     }
 
-    fn calc(&self) -> Result<u64> {
-        Ok(self.time_stamp - ONE_MONTH - ONE_DAY - EIGHT_HOURS)
+    #[cfg(feature = "tz")]
+    #[inline(always)]
+    pub(super) fn _zone_offset(zone: &str, time_stamp: i64) -> Result<i32> {
+        super::tzdb_offset(zone, time_stamp)
     }
 }
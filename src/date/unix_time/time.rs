@@ -9,15 +9,25 @@ pub(crate) struct TimeCalc {
 
 impl TimeCalc {
     #[inline(always)]
-    pub(super) fn _calc(&self) -> Result<u64> {
-        let Ok(dt) = Date::from_calendar_date(
-            self.year,
-            Month::try_from(self.month.unwrap_or(1)).unwrap(),
-            self.day.unwrap_or(1),
-        ) else {
+    pub(super) fn _calc(&self) -> Result<i64> {
+        let Ok(month) = Month::try_from(self.month.unwrap_or(1)) else {
+            return Err("Invalid date".to_string());
+        };
+        let Ok(dt) = Date::from_calendar_date(self.year, month, self.day.unwrap_or(1)) else {
             return Err("Invalid date".to_string());
         };
         let dt = PrimitiveDateTime::new(dt, Time::from_hms(0, 0, 0).expect("Internal error"));
-        Ok(dt.assume_utc().unix_timestamp() as u64)
+        Ok(dt.assume_utc().unix_timestamp())
+    }
+
+    #[inline(always)]
+    pub(super) fn _local_offset(time_stamp: i64) -> Result<i32> {
+        super::system_local_offset(time_stamp)
+    }
+
+    #[cfg(feature = "tz")]
+    #[inline(always)]
+    pub(super) fn _zone_offset(zone: &str, time_stamp: i64) -> Result<i32> {
+        super::tzdb_offset(zone, time_stamp)
     }
 }
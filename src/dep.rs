@@ -0,0 +1,265 @@
+use crate::version::{parse_semver, parse_version, VersionCond};
+use crate::{manifest_dir, Result};
+use proc_macro2::token_stream::IntoIter;
+use proc_macro2::TokenTree;
+use semver::Version;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+/// A condition on a dependency in the `Cargo.lock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DepCond {
+    /// `dep: foo`, met when the dependency is in the lock file
+    Added(String),
+    /// `dep: !foo`, met when the dependency isn't in the lock file anymore
+    Removed(String),
+    /// `dep: foo >= 1.0.0`, met when any of the resolved versions of the dependency meets the condition
+    Version(String, VersionCond),
+}
+
+impl DepCond {
+    /// Checks the condition against the resolved packages, and describes why it's met if it is
+    pub(crate) fn check(&self, packages: &[Package]) -> Option<String> {
+        let versions = |name: &str| {
+            packages
+                .iter()
+                .filter(move |p| p.name == name)
+                .map(|p| &p.version)
+                .collect::<Vec<_>>()
+        };
+        match self {
+            DepCond::Added(name) => {
+                let versions = versions(name);
+                let version = versions.first()?;
+                Some(format!(
+                    "The dependency `{}` is added with version `{}`",
+                    name, version
+                ))
+            }
+            DepCond::Removed(name) => versions(name)
+                .is_empty()
+                .then(|| format!("The dependency `{}` is removed", name)),
+            DepCond::Version(name, cond) => {
+                let version = versions(name).into_iter().find(|v| cond.is_met(v))?;
+                Some(format!(
+                    "The dependency `{}` version `{}` meets `{}`",
+                    name, version, cond
+                ))
+            }
+        }
+    }
+}
+
+/// Parses a dependency condition, e.g. `serde >= 1.0.200`, `!foo` or `foo`
+pub(crate) fn parse_dep(tokens: &mut Peekable<IntoIter>) -> Result<DepCond> {
+    let removed = matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '!');
+    if removed {
+        let _ = tokens.next();
+    }
+
+    // `tokio-util` is tokenized as `tokio`, `-` and `util`
+    let mut name = String::new();
+    while let Some(nt) = tokens.peek() {
+        match nt {
+            TokenTree::Ident(ident) => name.push_str(&ident.to_string()),
+            TokenTree::Punct(punct) if punct.as_char() == '-' && !name.is_empty() => name.push('-'),
+            _ => break,
+        }
+        let _ = tokens.next();
+    }
+    if name.is_empty() || name.ends_with('-') {
+        return Err("Expected a dependency name, e.g. `dep: serde >= 1.0.200`".to_string());
+    }
+
+    let has_version = match tokens.peek() {
+        Some(TokenTree::Punct(punct)) => punct.as_char() != ',' && punct.as_char() != ';',
+        _ => false,
+    };
+    if !has_version {
+        return Ok(if removed {
+            DepCond::Removed(name)
+        } else {
+            DepCond::Added(name)
+        });
+    }
+    if removed {
+        return Err(format!(
+            "A removed dependency can't have a version, use `dep: !{}` alone",
+            name
+        ));
+    }
+    Ok(DepCond::Version(name, parse_version(tokens)?))
+}
+
+/// A resolved package in the `Cargo.lock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Package {
+    pub(crate) name: String,
+    pub(crate) version: Version,
+}
+
+/// Finds the `Cargo.lock` of the crate that's being compiled, it's in the crate directory or in the workspace root
+pub(crate) fn find_lockfile() -> Result<PathBuf> {
+    lockfile_in(&manifest_dir()?).ok_or_else(|| {
+        "Can't find the `Cargo.lock`, try to run `cargo generate-lockfile`".to_string()
+    })
+}
+
+pub(crate) fn lockfile_in(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+/// Reads the resolved packages from a `Cargo.lock` file
+pub(crate) fn read_lockfile(path: &Path) -> Result<Vec<Package>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can't read `{}`: {}", path.display(), e))?;
+    parse_lockfile(&content)
+}
+
+/// Parses the `[[package]]` tables of a `Cargo.lock`, cargo always writes their `name` and `version` keys as plain strings
+fn parse_lockfile(content: &str) -> Result<Vec<Package>> {
+    let mut packages = Vec::new();
+    let mut name = None;
+    let mut version = None;
+    let mut push = |name: &mut Option<String>, version: &mut Option<String>| -> Result<()> {
+        if let (Some(name), Some(version)) = (name.take(), version.take()) {
+            let version = parse_semver(&version)?;
+            packages.push(Package { name, version });
+        }
+        Ok(())
+    };
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            push(&mut name, &mut version)?;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "name" => name = Some(value),
+            "version" => version = Some(value),
+            _ => {}
+        }
+    }
+    push(&mut name, &mut version)?;
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_tokens;
+    use quote::quote;
+
+    const LOCKFILE: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "serde"
+version = "1.0.197"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "3fb1c873e1b9b056a4dc4c0c198b24c3ffa059243875552b2bd0933b1aee4ce2"
+dependencies = [
+ "serde_derive",
+]
+
+[[package]]
+name = "tokio-util"
+version = "0.7.10"
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+
+[[package]]
+name = "syn"
+version = "2.0.52"
+"#;
+
+    #[test]
+    fn test_parse_lockfile() -> Result<()> {
+        let packages = parse_lockfile(LOCKFILE)?;
+        let packages = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            packages,
+            vec![
+                ("serde", "1.0.197".to_string()),
+                ("tokio-util", "0.7.10".to_string()),
+                ("syn", "1.0.109".to_string()),
+                ("syn", "2.0.52".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_dep() -> Result<()> {
+        assert_eq!(
+            parse_tokens(parse_dep, quote!(serde))?,
+            DepCond::Added("serde".to_string())
+        );
+        assert_eq!(
+            parse_tokens(parse_dep, quote!(!tokio - util))?,
+            DepCond::Removed("tokio-util".to_string())
+        );
+        let DepCond::Version(name, cond) = parse_tokens(parse_dep, quote!(serde_json >= 1.0.200))?
+        else {
+            panic!("Expected a version condition");
+        };
+        assert_eq!(name, "serde_json");
+        assert_eq!(cond.to_string(), ">= 1.0.200");
+        assert!(parse_tokens(parse_dep, quote!(!serde >= 1.0)).is_err());
+        assert!(parse_tokens(parse_dep, quote!(>= 1.0)).is_err());
+        assert!(parse_tokens(parse_dep, quote!(tokio-)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dep_check() -> Result<()> {
+        let packages = parse_lockfile(LOCKFILE)?;
+        assert!(parse_tokens(parse_dep, quote!(serde >= 1.0.200))?
+            .check(&packages)
+            .is_none());
+        assert!(parse_tokens(parse_dep, quote!(serde >= 1.0.197))?
+            .check(&packages)
+            .is_some());
+        // any of the resolved versions
+        assert_eq!(
+            parse_tokens(parse_dep, quote!(syn >= 2))?.check(&packages),
+            Some("The dependency `syn` version `2.0.52` meets `>= 2.0.0`".to_string())
+        );
+        assert!(parse_tokens(parse_dep, quote!(tokio - util))?
+            .check(&packages)
+            .is_some());
+        assert!(parse_tokens(parse_dep, quote!(!tokio - util))?
+            .check(&packages)
+            .is_none());
+        assert!(parse_tokens(parse_dep, quote!(!foo))?
+            .check(&packages)
+            .is_some());
+        assert!(parse_tokens(parse_dep, quote!(foo))?
+            .check(&packages)
+            .is_none());
+        assert!(parse_tokens(parse_dep, quote!(foo >= 0.0.0))?
+            .check(&packages)
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_lockfile() {
+        let lockfile = lockfile_in(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src/date"));
+        assert_eq!(
+            lockfile,
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock"))
+        );
+    }
+}
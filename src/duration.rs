@@ -0,0 +1,118 @@
+use crate::Result;
+use proc_macro2::token_stream::IntoIter;
+use proc_macro2::TokenTree;
+use std::iter::Peekable;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+
+/// Parses a duration, e.g. `30 days`, `12h` or `2 weeks`, and returns it in seconds.
+///
+/// A month is 30 days.
+pub(crate) fn parse_duration(tokens: &mut Peekable<IntoIter>) -> Result<u64> {
+    let Some(TokenTree::Literal(lit)) = tokens.next() else {
+        return Err("Expected a duration, e.g. `30 days`".to_string());
+    };
+    // `30d` is a single literal with the `d` suffix
+    let lit = lit.to_string();
+    let digits = lit.find(|c: char| !c.is_ascii_digit()).unwrap_or(lit.len());
+    let (amount, suffix) = lit.split_at(digits);
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration `{}`", lit))?;
+    let unit = if suffix.is_empty() {
+        match tokens.next() {
+            Some(TokenTree::Ident(unit)) => unit.to_string(),
+            _ => return Err(format!("Expected a unit after `{}`, e.g. `days`", amount)),
+        }
+    } else {
+        suffix.to_string()
+    };
+    let unit = match unit.as_str() {
+        "h" | "hour" | "hours" => HOUR,
+        "d" | "day" | "days" => DAY,
+        "w" | "week" | "weeks" => 7 * DAY,
+        "month" | "months" => 30 * DAY,
+        _ => {
+            return Err(format!(
+                "Unknown unit `{}`, expected `h`, `d`, `w` or `months`",
+                unit
+            ))
+        }
+    };
+    amount
+        .checked_mul(unit)
+        .ok_or_else(|| format!("The duration `{}` is too long", lit))
+}
+
+/// Parses a duration from a string, e.g. the value of an environment variable
+pub(crate) fn parse_duration_str(s: &str) -> Result<u64> {
+    let tokens = s
+        .parse::<proc_macro2::TokenStream>()
+        .map_err(|_| format!("Invalid duration `{}`", s))?;
+    let mut tokens = tokens.into_iter().peekable();
+    let duration = parse_duration(&mut tokens)?;
+    if tokens.next().is_some() {
+        return Err(format!("Invalid duration `{}`", s));
+    }
+    Ok(duration)
+}
+
+/// Formats the time that's left before a deadline, rounded down to days, or to hours if it's less than a day
+pub(crate) fn time_left(secs: u64) -> String {
+    let (amount, unit) = match secs {
+        DAY.. => (secs / DAY, "day"),
+        HOUR.. => (secs / HOUR, "hour"),
+        _ => return "less than an hour".to_string(),
+    };
+    if amount == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", amount, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_tokens;
+    use quote::quote;
+
+    #[test]
+    fn test_parse_duration() -> Result<()> {
+        assert_eq!(parse_tokens(parse_duration, quote!(30 days))?, 30 * DAY);
+        assert_eq!(parse_tokens(parse_duration, quote!(1 day))?, DAY);
+        assert_eq!(parse_tokens(parse_duration, quote!(30d))?, 30 * DAY);
+        assert_eq!(parse_tokens(parse_duration, quote!(12h))?, 12 * HOUR);
+        assert_eq!(parse_tokens(parse_duration, quote!(2 weeks))?, 14 * DAY);
+        assert_eq!(parse_tokens(parse_duration, quote!(1w))?, 7 * DAY);
+        assert_eq!(parse_tokens(parse_duration, quote!(6 months))?, 180 * DAY);
+        assert!(parse_tokens(parse_duration, quote!(30)).is_err());
+        assert!(parse_tokens(parse_duration, quote!(30m)).is_err());
+        assert!(parse_tokens(parse_duration, quote!(30 years)).is_err());
+        assert!(parse_tokens(parse_duration, quote!(days)).is_err());
+        assert!(parse_tokens(parse_duration, quote!(1.5 days)).is_err());
+        assert!(parse_tokens(parse_duration, quote!(99999999999999999 months)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration_str() -> Result<()> {
+        assert_eq!(parse_duration_str("14 days")?, 14 * DAY);
+        assert_eq!(parse_duration_str(" 2w ")?, 14 * DAY);
+        assert!(parse_duration_str("").is_err());
+        assert!(parse_duration_str("14 days ago").is_err());
+        assert!(parse_duration_str("\"14 days").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_left() {
+        assert_eq!(time_left(0), "less than an hour");
+        assert_eq!(time_left(HOUR - 1), "less than an hour");
+        assert_eq!(time_left(HOUR), "1 hour");
+        assert_eq!(time_left(DAY - 1), "23 hours");
+        assert_eq!(time_left(DAY), "1 day");
+        assert_eq!(time_left(14 * DAY + 23 * HOUR), "14 days");
+    }
+}
@@ -0,0 +1,191 @@
+use crate::Result;
+use proc_macro2::token_stream::IntoIter;
+use proc_macro2::{Spacing, TokenTree};
+use std::iter::Peekable;
+
+/// A condition on an environment variable of the build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EnvCond {
+    pub(crate) name: String,
+    check: EnvCheck,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EnvCheck {
+    /// `env: NAME`
+    Set,
+    /// `env: !NAME`
+    Unset,
+    /// `env: NAME == "value"`
+    Eq(String),
+    /// `env: NAME != "value"`, it's also met when the variable isn't set
+    Ne(String),
+}
+
+impl EnvCond {
+    /// Checks the condition against the value of the variable, and describes why it's met if it is
+    pub(crate) fn check(&self, value: Option<&str>) -> Option<String> {
+        let name = &self.name;
+        match (&self.check, value) {
+            (EnvCheck::Set, Some(value)) => Some(format!(
+                "The environment variable `{}` is set to `{}`",
+                name, value
+            )),
+            (EnvCheck::Unset, None) => {
+                Some(format!("The environment variable `{}` isn't set", name))
+            }
+            (EnvCheck::Eq(expected), Some(value)) if value == expected => Some(format!(
+                "The environment variable `{}` is `{}`",
+                name, value
+            )),
+            (EnvCheck::Ne(expected), value) if value != Some(expected.as_str()) => {
+                Some(match value {
+                    Some(value) => format!(
+                        "The environment variable `{}` is `{}` not `{}`",
+                        name, value, expected
+                    ),
+                    None => format!(
+                        "The environment variable `{}` isn't set to `{}`",
+                        name, expected
+                    ),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks the condition against the environment of the compiler
+    pub(crate) fn eval(&self) -> Option<String> {
+        self.check(std::env::var(&self.name).ok().as_deref())
+    }
+}
+
+/// Parses an environment variable condition, e.g. `RELEASE_CHANNEL == "stable"`, `CI` or `!CI`
+pub(crate) fn parse_env(tokens: &mut Peekable<IntoIter>) -> Result<EnvCond> {
+    let unset = matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '!');
+    if unset {
+        let _ = tokens.next();
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        Some(TokenTree::Literal(lit)) => str_lit(&lit.to_string())?,
+        _ => {
+            return Err(
+                "Expected an environment variable name, e.g. `env: RELEASE_CHANNEL`".to_string(),
+            )
+        }
+    };
+
+    let mut op = String::new();
+    while let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        if punct.as_char() == ',' || punct.as_char() == ';' {
+            break;
+        }
+        op.push(punct.as_char());
+        let joint = punct.spacing() == Spacing::Joint;
+        let _ = tokens.next();
+        if !joint {
+            break;
+        }
+    }
+    if op.is_empty() {
+        let check = if unset {
+            EnvCheck::Unset
+        } else {
+            EnvCheck::Set
+        };
+        return Ok(EnvCond { name, check });
+    }
+    if unset {
+        return Err(format!(
+            "An unset variable can't be compared, use `env: !{}` alone",
+            name
+        ));
+    }
+    let value = match tokens.next() {
+        Some(TokenTree::Literal(lit)) => str_lit(&lit.to_string())?,
+        _ => return Err(format!("Expected a string to compare `{}` with", name)),
+    };
+    let check = match op.as_str() {
+        "==" => EnvCheck::Eq(value),
+        "!=" => EnvCheck::Ne(value),
+        _ => return Err(format!("Expected `==` or `!=`, found `{}`", op)),
+    };
+    Ok(EnvCond { name, check })
+}
+
+/// Unquotes a plain string literal, without escapes
+pub(crate) fn str_lit(lit: &str) -> Result<String> {
+    lit.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .filter(|s| !s.contains('\\'))
+        .map(str::to_string)
+        .ok_or_else(|| format!("Expected a plain string, found `{}`", lit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_tokens;
+    use quote::quote;
+
+    #[test]
+    fn test_parse_env() -> Result<()> {
+        assert_eq!(
+            parse_tokens(parse_env, quote!(CI))?,
+            EnvCond {
+                name: "CI".to_string(),
+                check: EnvCheck::Set
+            }
+        );
+        assert_eq!(parse_tokens(parse_env, quote!(!CI))?.check, EnvCheck::Unset);
+        assert_eq!(
+            parse_tokens(parse_env, quote!(RELEASE_CHANNEL == "stable"))?.check,
+            EnvCheck::Eq("stable".to_string())
+        );
+        assert_eq!(
+            parse_tokens(parse_env, quote!("RELEASE_CHANNEL" != "dev"))?.check,
+            EnvCheck::Ne("dev".to_string())
+        );
+        assert!(parse_tokens(parse_env, quote!()).is_err());
+        assert!(parse_tokens(parse_env, quote!(!CI == "true")).is_err());
+        assert!(parse_tokens(parse_env, quote!(CI = "true")).is_err());
+        assert!(parse_tokens(parse_env, quote!(CI >= "1")).is_err());
+        assert!(parse_tokens(parse_env, quote!(CI == true)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_check() -> Result<()> {
+        let stable = parse_tokens(parse_env, quote!(RELEASE_CHANNEL == "stable"))?;
+        assert!(stable.check(Some("stable")).is_some());
+        assert!(stable.check(Some("dev")).is_none());
+        assert!(stable.check(None).is_none());
+
+        let not_stable = parse_tokens(parse_env, quote!(RELEASE_CHANNEL != "stable"))?;
+        assert!(not_stable.check(Some("stable")).is_none());
+        assert!(not_stable.check(Some("dev")).is_some());
+        assert!(not_stable.check(None).is_some());
+
+        assert!(parse_tokens(parse_env, quote!(CI))?
+            .check(Some(""))
+            .is_some());
+        assert!(parse_tokens(parse_env, quote!(CI))?.check(None).is_none());
+        assert!(parse_tokens(parse_env, quote!(!CI))?.check(None).is_some());
+        assert!(parse_tokens(parse_env, quote!(!CI))?
+            .check(Some("true"))
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_eval() -> Result<()> {
+        assert!(parse_tokens(parse_env, quote!(CARGO_PKG_NAME == "todo2"))?
+            .eval()
+            .is_some());
+        assert!(parse_tokens(parse_env, quote!(TODO2_SURELY_NOT_SET))?
+            .eval()
+            .is_none());
+        Ok(())
+    }
+}
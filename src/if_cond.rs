@@ -1,7 +1,8 @@
-use crate::Result;
+use crate::env_cond::str_lit;
+use crate::{Result, SpannedError};
 use proc_macro2::token_stream::IntoIter;
-use proc_macro2::{TokenStream, TokenTree};
-use quote::TokenStreamExt;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{quote, TokenStreamExt};
 use std::iter::Peekable;
 
 pub(crate) fn parse_if(tokens: &mut Peekable<IntoIter>) -> Result<TokenStream> {
@@ -17,3 +18,387 @@ pub(crate) fn parse_if(tokens: &mut Peekable<IntoIter>) -> Result<TokenStream> {
     }
     Ok(rt)
 }
+
+/// The `if` condition after evaluating it at compile time
+pub(crate) struct StaticIf {
+    /// The condition as a `cfg` predicate, so the compiler decides the `cfg!` parts for the crate that uses the macro
+    pub(crate) predicate: TokenStream,
+    /// The environment variables that the condition reads, they need to be tracked to rebuild when they change
+    pub(crate) env: Vec<String>,
+}
+
+/// Evaluates the `if` condition at compile time if it only consists of `cfg!(...)`, `env!`/`option_env!` comparisons
+/// and literal booleans, combined with `!`, `&&`, `||` and parentheses.
+///
+/// Returns `None` if any part of it is only known at runtime,
+/// or an error if it compares an `env!` string with an `option_env!` option, it wouldn't compile at runtime either.
+pub(crate) fn eval_if(cond: &TokenStream) -> std::result::Result<Option<StaticIf>, SpannedError> {
+    let tokens = cond.clone().into_iter().collect::<Vec<_>>();
+    let mut eval = Evaluator {
+        tokens: &tokens,
+        pos: 0,
+        env: Vec::new(),
+        error: None,
+    };
+    let predicate = eval.or();
+    if let Some(error) = eval.error {
+        return Err(error);
+    }
+    match predicate {
+        Some(predicate) if eval.pos == tokens.len() => Ok(Some(StaticIf {
+            predicate,
+            env: eval.env,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// A value that can be compared in the condition
+#[derive(PartialEq)]
+enum Value {
+    Str(String),
+    Opt(Option<String>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "&str",
+            Value::Opt(_) => "Option<&str>",
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    tokens: &'a [TokenTree],
+    pos: usize,
+    env: Vec<String>,
+    /// A comparison that can't be evaluated, it stops the evaluation
+    error: Option<SpannedError>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn or(&mut self) -> Option<TokenStream> {
+        let mut operands = vec![self.and()?];
+        while self.eat_double('|') {
+            operands.push(self.and()?);
+        }
+        Some(if operands.len() == 1 {
+            operands.pop()?
+        } else {
+            quote!(any(#(#operands),*))
+        })
+    }
+
+    fn and(&mut self) -> Option<TokenStream> {
+        let mut operands = vec![self.unary()?];
+        while self.eat_double('&') {
+            operands.push(self.unary()?);
+        }
+        Some(if operands.len() == 1 {
+            operands.pop()?
+        } else {
+            quote!(all(#(#operands),*))
+        })
+    }
+
+    fn unary(&mut self) -> Option<TokenStream> {
+        if let Some(TokenTree::Punct(punct)) = self.peek() {
+            if punct.as_char() == '!' && punct.spacing() == Spacing::Alone {
+                self.pos += 1;
+                let operand = self.unary()?;
+                return Some(quote!(not(#operand)));
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<TokenStream> {
+        match self.peek()? {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                self.pos += 1;
+                let inner = match eval_if(&group.stream()) {
+                    Ok(inner) => inner?,
+                    Err(error) => {
+                        self.error = Some(error);
+                        return None;
+                    }
+                };
+                self.env.extend(inner.env);
+                Some(inner.predicate)
+            }
+            TokenTree::Ident(ident) if ident == "true" => {
+                self.pos += 1;
+                Some(quote!(all()))
+            }
+            TokenTree::Ident(ident) if ident == "false" => {
+                self.pos += 1;
+                Some(quote!(any()))
+            }
+            _ => {
+                let start = self.pos;
+                if let Some((name, args)) = self.macro_call() {
+                    if name == "cfg" && !args.is_empty() {
+                        return Some(args);
+                    }
+                }
+                self.pos = start;
+                let is_true = self.comparison()?;
+                Some(if is_true {
+                    quote!(all())
+                } else {
+                    quote!(any())
+                })
+            }
+        }
+    }
+
+    /// Evaluates `env!`/`option_env!` comparisons, e.g. `env!("CHANNEL") == "stable"` or `option_env!("CI").is_some()`
+    fn comparison(&mut self) -> Option<bool> {
+        let lhs = self.value()?;
+        if let Value::Opt(opt) = &lhs {
+            if self.eat_punct('.') {
+                let TokenTree::Ident(method) = self.next()? else {
+                    return None;
+                };
+                let TokenTree::Group(args) = self.next()? else {
+                    return None;
+                };
+                if !args.stream().is_empty() {
+                    return None;
+                }
+                return match method.to_string().as_str() {
+                    "is_some" => Some(opt.is_some()),
+                    "is_none" => Some(opt.is_none()),
+                    _ => None,
+                };
+            }
+        }
+        let span = self.peek()?.span();
+        let eq = if self.eat_double('=') {
+            true
+        } else if self.eat_pair('!', '=') {
+            false
+        } else {
+            return None;
+        };
+        let rhs = self.value()?;
+        if lhs.type_name() != rhs.type_name() {
+            self.error = Some(SpannedError {
+                message: format!(
+                    "Can't compare `{}` with `{}`, `env!` returns `&str` and `option_env!` returns `Option<&str>`",
+                    lhs.type_name(),
+                    rhs.type_name()
+                ),
+                span,
+            });
+            return None;
+        }
+        Some((lhs == rhs) == eq)
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        match self.peek()? {
+            TokenTree::Literal(lit) => {
+                self.pos += 1;
+                Some(Value::Str(str_lit(&lit.to_string()).ok()?))
+            }
+            TokenTree::Ident(ident) if ident == "None" => {
+                self.pos += 1;
+                Some(Value::Opt(None))
+            }
+            TokenTree::Ident(ident) if ident == "Some" => {
+                self.pos += 1;
+                let TokenTree::Group(group) = self.next()? else {
+                    return None;
+                };
+                let TokenTree::Literal(lit) = group.stream().into_iter().next()? else {
+                    return None;
+                };
+                Some(Value::Opt(Some(str_lit(&lit.to_string()).ok()?)))
+            }
+            _ => {
+                let (name, args) = self.macro_call()?;
+                let args = args.into_iter().collect::<Vec<_>>();
+                let [TokenTree::Literal(var)] = args.as_slice() else {
+                    return None;
+                };
+                let var = str_lit(&var.to_string()).ok()?;
+                let value = std::env::var(&var).ok();
+                self.env.push(var);
+                match name.as_str() {
+                    // An unset variable is a compile error from `env!` itself, so leave it to the runtime check
+                    "env" => Some(Value::Str(value?)),
+                    "option_env" => Some(Value::Opt(value)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Parses a macro call with an optional `::core::`/`::std::` path, and returns its name and arguments
+    fn macro_call(&mut self) -> Option<(String, TokenStream)> {
+        self.eat_double(':');
+        if let Some(TokenTree::Ident(ident)) = self.peek() {
+            if ident == "core" || ident == "std" {
+                self.pos += 1;
+                if !self.eat_double(':') {
+                    return None;
+                }
+            }
+        }
+        let TokenTree::Ident(name) = self.next()? else {
+            return None;
+        };
+        if !self.eat_punct('!') {
+            return None;
+        }
+        let TokenTree::Group(args) = self.next()? else {
+            return None;
+        };
+        Some((name.to_string(), args.stream()))
+    }
+
+    fn peek(&self) -> Option<&'a TokenTree> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&'a TokenTree> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1)
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        match self.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == c => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Eats a two characters operator, e.g. `&&` or `!=`
+    fn eat_pair(&mut self, first: char, second: char) -> bool {
+        match (self.peek(), self.tokens.get(self.pos + 1)) {
+            (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                if a.as_char() == first
+                    && a.spacing() == Spacing::Joint
+                    && b.as_char() == second =>
+            {
+                self.pos += 2;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn eat_double(&mut self, c: char) -> bool {
+        self.eat_pair(c, c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(cond: TokenStream) -> Option<String> {
+        match eval_if(&cond) {
+            Ok(static_if) => static_if.map(|s| s.predicate.to_string()),
+            Err(err) => panic!("{}", err.message),
+        }
+    }
+
+    fn eval_err(cond: TokenStream) -> String {
+        match eval_if(&cond) {
+            Ok(_) => panic!("Expected an error"),
+            Err(err) => err.message,
+        }
+    }
+
+    #[test]
+    fn test_eval_cfg() {
+        assert_eq!(
+            eval(quote!(cfg!(debug_assertions))),
+            Some(quote!(debug_assertions).to_string())
+        );
+        assert_eq!(
+            eval(quote!(!cfg!(debug_assertions))),
+            Some(quote!(not(debug_assertions)).to_string())
+        );
+        assert_eq!(
+            eval(quote!(
+                ::core::cfg!(feature = "foo") && (cfg!(unix) || !std::cfg!(test))
+            )),
+            Some(quote!(all(feature = "foo", any(unix, not(test)))).to_string())
+        );
+    }
+
+    #[test]
+    fn test_eval_literal_booleans() {
+        assert_eq!(eval(quote!(true)), Some(quote!(all()).to_string()));
+        assert_eq!(
+            eval(quote!(false || cfg!(unix))),
+            Some(quote!(any(any(), unix)).to_string())
+        );
+    }
+
+    #[test]
+    fn test_eval_env() {
+        // Cargo always sets it for the crate that's being compiled
+        assert_eq!(
+            eval(quote!(env!("CARGO_PKG_NAME") == "todo2")),
+            Some(quote!(all()).to_string())
+        );
+        assert_eq!(
+            eval(quote!("todo2" != env!("CARGO_PKG_NAME"))),
+            Some(quote!(any()).to_string())
+        );
+        assert_eq!(
+            eval(quote!(option_env!("CARGO_PKG_NAME") == Some("todo2"))),
+            Some(quote!(all()).to_string())
+        );
+        assert_eq!(
+            eval(quote!(
+                option_env!("TODO2_SURELY_NOT_SET").is_none() && cfg!(unix)
+            )),
+            Some(quote!(all(all(), unix)).to_string())
+        );
+        assert_eq!(
+            eval(quote!(option_env!("TODO2_SURELY_NOT_SET") == None)),
+            Some(quote!(all()).to_string())
+        );
+        let static_if = eval_if(&quote!(
+            option_env!("A").is_some() || option_env!("B") == Some("b")
+        ))
+        .unwrap_or_else(|err| panic!("{}", err.message))
+        .unwrap();
+        assert_eq!(static_if.env, vec!["A".to_string(), "B".to_string()]);
+    }
+
+    #[test]
+    fn test_eval_type_mismatch() {
+        assert_eq!(
+            eval_err(quote!(env!("CARGO_PKG_NAME") == Some("todo2"))),
+            "Can't compare `&str` with `Option<&str>`, `env!` returns `&str` and `option_env!` returns `Option<&str>`"
+        );
+        assert_eq!(
+            eval_err(quote!(cfg!(unix) && (option_env!("CARGO_PKG_NAME") != "todo2"))),
+            "Can't compare `Option<&str>` with `&str`, `env!` returns `&str` and `option_env!` returns `Option<&str>`"
+        );
+        assert_eq!(
+            eval_err(quote!(None == "todo2")),
+            "Can't compare `Option<&str>` with `&str`, `env!` returns `&str` and `option_env!` returns `Option<&str>`"
+        );
+    }
+
+    #[test]
+    fn test_eval_runtime_conditions() {
+        assert!(eval(quote!(a == 1)).is_none());
+        assert!(eval(quote!(cfg!(unix) && a == 1)).is_none());
+        assert!(eval(quote!(1 == 1)).is_none());
+        assert!(eval(quote!(env!("TODO2_SURELY_NOT_SET") == "x")).is_none());
+        assert!(eval(quote!(is_ready())).is_none());
+        assert!(eval(quote!(cfg!(unix) foo)).is_none());
+    }
+}
@@ -23,32 +23,38 @@
 //! ```
 //!
 //! ```rust,should_panic
-//! #[macro_use]
-//! extern crate todo2;
+//! use todo2::todo;
 //!
-//! fn main() {
-//!   todo!("Hack NASA", by: 2024-3-26 at 9:00);
-//!   get_a_hot_gf(true)
-//! }
+//! todo!("Hack NASA", by: 2024-3-26 at 9:00);
+//! get_a_hot_gf(true);
 //!
 //! fn get_a_hot_gf(single: bool) {
-//!  todo!("Get a hot girlfriend", if: single);
+//!     todo!("Get a hot girlfriend", if: single);
 //! }
 //! ```
 //!
 //! # Features
 //! - `log` - Just logs an error instead of panicking or emitting a compile error, this may useful in the serious projects, this feature respects that you have added the `log` crate to your dependencies
+//! - `log-kv` - Enables `log` and attaches the deadline, the source location and the metadata to the records as structured key-values, you need the `kv` feature of the `log` crate. [Read more](#structured-key-values-in-the-log-records)
+//! - `tracing` - Emits `tracing` events with structured fields instead of panicking, you need the `tracing` crate in your dependencies. [Read more](#using-the-tracing-feature)
 //! - `compile-error` - Emits a compile error instead of panicking.
-//! - `with-chrono` - Enables the `chrono` this enables you to specify the deadline for the `by` condition using the [`chrono::Utc`](https://docs.rs/chrono/latest/chrono/struct.Utc.html) or [`chrono::DateTime`](https://docs.rs/chrono/latest/chrono/struct.DateTime.html) types. [not implemented yet](#maybe)
-//! - `with-time` - Enables the `time` this enables you to specify the deadline for the `by` condition using the [`time::OffsetDateTime`](https://docs.rs/time/latest/time/struct.OffsetDateTime.html) type or the [`time::macros::datetime`](https://docs.rs/time/0.3.28/time/macros/macro.datetime.html) macro. [not implemented yet](#maybe)
-//! - `and-time` - allows you to specify a specific time of the day in the `by` condition
+//! - `eprint` - Prints a line to the standard error once per call site instead of panicking, for the small binaries that don't set up a logger. [Read more](#using-the-eprint-feature)
+//! - `with-chrono` - Enables the `chrono` this enables you to specify the deadline for the `by` condition using the [`chrono::Utc`](https://docs.rs/chrono/latest/chrono/struct.Utc.html) or [`chrono::DateTime`](https://docs.rs/chrono/latest/chrono/struct.DateTime.html) types. [Read more](#using-the-with-chrono-feature)
+//! - `with-time` - Enables the `time` this enables you to specify the deadline for the `by` condition using the [`time::OffsetDateTime`](https://docs.rs/time/latest/time/struct.OffsetDateTime.html) type or the [`time::macros::datetime`](https://docs.rs/time/0.3.28/time/macros/macro.datetime.html) macro. [Read more](#using-the-with-time-feature)
+//! - `no-std` - doesn't use `std` in the generated code, the deadlines are checked at compile time or with your own clock. [Read more](#no_std-support)
+//! - `test-clock` - lets the `TODO2_NOW` variable override the clock at runtime, to test what happens after the deadlines. [Read more](#testing-after-the-deadlines)
+//! - `and-time` - allows you to specify a specific time of the day in the `by` condition, e.g. `at 9:30`, `at 5:30pm` or `at noon`. [Read more](#time-in-the-by-condition)
+//! - `local-tz` - allows you to use the time zone of the build machine in the `by` condition, e.g. `by: 2025-03-01 at 9:00 local`, the `tz` feature enables it too, and the `chrono-backend` doesn't need it. [Read more](#time-in-the-by-condition)
+//! - `tz` - allows you to specify an IANA time zone name in the `by` condition, e.g. `by: 2025-06-01 at 17:00 in Europe/Berlin`. the time zone database is embedded, so it doesn't depend on the build machine. [Read more](#time-in-the-by-condition)
+//! - `chrono-tz` - the `tz` feature for the `chrono-backend`, it resolves the time zone names with the `chrono-tz` database instead, and it enables the `chrono-backend`. [Read more](#time-in-the-by-condition)
+//! - `nightly` - emits the `warn_before` warnings with the unstable `proc_macro::Diagnostic` API, it requires a nightly compiler. [Read more](#warnings-before-the-deadline)
 //! - `original-compatibility` - Allows you to use this macro without pass any arguments, or with only the message.
 //! - `strict-syntax` - Enables the strict syntax,, just too force you to put a comma or a semicolon after the message.
-//! - `chrono-backend` - Use the `chrono` as the backend instead of the default implementation for the `by` condition to calculate the unix time stamp. I prefer to enable this feature if I have chrono in the dependencies, because it's more accurate than the default implementation. [Read more](#backends)
-//! - `time-backend` - Use the `time` as the backend instead of the default implementation for the `by` condition to calculate the unix time stamp. I prefer to enable this feature if I have time in the dependencies, because it's more accurate than the default implementation. [Read more](#backends)
+//! - `chrono-backend` - Use the `chrono` as the backend instead of the default implementation for the `by` condition to calculate the unix time stamp. they give the same results, so it's only useful if you want to double check it. [Read more](#backends)
+//! - `time-backend` - Use the `time` as the backend instead of the default implementation for the `by` condition to calculate the unix time stamp. they give the same results, so it's only useful if you want to double check it. [Read more](#backends)
 //! - `am-cool` - To indicate that you are cool. I love you.
 //!
-//! The default features are: `original-compatibility`, `strict-syntax`, `and-time`, `time-backend`.
+//! The default features are: `original-compatibility`, `strict-syntax`, `and-time`.
 //!
 //! # Examples
 //! ## Using the `log` feature
@@ -83,6 +89,53 @@
 //! ```
 //! consider that you can't enable the `compile-error` feature with the `log` feature. only one of them can be enabled at a time.
 //!
+//! ## Structured key-values in the `log` records
+//! the `log-kv` feature attaches these key-values to the records, so a structured logger like `structured-logger` can index them:
+//! - `kind` - the condition, e.g. `by` or `if`.
+//! - `deadline` and `overdue_secs` - the deadline in RFC 3339 and how many seconds it has passed, only for the `by` and `in` conditions.
+//! - `file` and `line` - where the `todo!` is.
+//! - `owner` and `ticket` - the metadata of the TODO, if it has them.
+//!
+//! it needs the `kv` feature of the `log` crate, the `log` feature alone doesn't:
+//! ```toml
+//! [dependencies]
+//! log = { version = "0.4.21", features = ["kv"] }
+//! todo2 = { version = "0.1.0", features = ["log-kv"] }
+//! ```
+//! set the metadata with the `owner` and the `ticket` keys, the `tracing` events have them too:
+//! ```rust,ignore
+//! todo!("Drop the v1 API", by: 2024-06-01, owner: "alice", ticket: "API-123");
+//! ```
+//!
+//! ## Using the `tracing` feature
+//! enable the `tracing` feature and add the `tracing` crate to your dependencies:
+//! ```toml
+//! [dependencies]
+//! tracing = "0.1.40"
+//! todo2 = { version = "0.1.0", features = ["tracing"] }
+//! ```
+//! then a met condition emits an `error` event, it's emitted in the current span, so you see the expired TODO with its context in your trace viewer.
+//! the event has these fields:
+//! - `todo.message` - the message, the same as the event message.
+//! - `todo.deadline` - the deadline in RFC 3339, e.g. `2024-06-01T00:00:00Z`, only for the `by` and `in` conditions, except the `by` expressions of `with-chrono` and `with-time`.
+//! - `todo.kind` - the condition, e.g. `by` or `if`.
+//! - `todo.file` and `todo.line` - where the `todo!` is.
+//! - `todo.owner` and `todo.ticket` - the [metadata](#structured-key-values-in-the-log-records) of the TODO, if it has them.
+//!
+//! with [`warn_before`](#warnings-before-the-deadline), it also emits a `warn` event when the deadline is close at runtime.
+//! consider that you can't enable the `tracing` feature with the `log` or the `compile-error` features.
+//!
+//! ## Using the `eprint` feature
+//! the `eprint` feature prints a line to the standard error when a condition is met, instead of panicking:
+//! ```sh
+//! cargo add todo2 --features eprint
+//! ```
+//! ```log
+//! todo2: overdue since 2024-03-26 (12 days): Hack NASA at src/main.rs:4
+//! ```
+//! it's printed only the first time that the `todo!` is met in the process, so a loop doesn't flood the terminal.
+//! consider that you can't enable the `eprint` feature with the `log`, `tracing` or the `compile-error` features.
+//!
 //! ## Using the `compile-error` feature
 //! First, add the crate to your `Cargo.toml` and enable the `compile-error` feature:
 //! ```sh
@@ -99,50 +152,270 @@
 //! ```
 //! this will emit a compile error like this, when u try to compile the code in release mode.
 //!
-//! # Time in the `by` condition
-//! by default, the `by` condition takes a raw date and parse it with our custom parser, which expects the date in the `YYYY-MM-DD` format and `YYYY-MM-DD at HH:MM` or `YYYY-MM-DD @ HH:MM` format if you have the `and-time` feature enabled.
-//! and then it calculates the unix time stamp in UTC, and then compares it with the current time stamp.
+//! ## Choosing the action per call
+//! the `log`, `tracing`, `eprint` and `compile-error` features choose the action for the whole crate, and since cargo unifies the features,
+//! one crate in your workspace that enables `compile-error` changes it for all of them. the `action` key overrides it for one call:
+//! ```rust,ignore
+//! use todo2::todo;
 //!
-//! this for the parsing part, noting interested here. just macros magic. the complexity comes when we want to calculate the unix time stamp from the parsed date.
-//! here the time zones and the daylight saving time and the leap seconds come to play.
-//! and I don't want to deal with this complexity 'cause I'm lazy and this is a "proc macro" not a normal crate witch means that it runs at compile time, and we all know that the rust compile times is so "fast" :) and I don't want to make it slower.
-//! so I implemented a simple algorithm to calculate the unix time stamp, which is not accurate, but it works. hmm, kinda.
+//! todo!("Rotate the keys", by: 2024-06-01, action: compile_error);
+//! todo!("Drop the old endpoint", version: >= 2.0.0, action: warn);
+//! todo!("Nice to have", by: 2024-06-01, action: ignore);
+//! ```
+//! - `panic` - panics at runtime.
+//! - `log` - logs an error at runtime, you need the `log` crate in your dependencies.
+//! - `tracing` - emits an error event at runtime, you need the `tracing` crate in your dependencies. [Read more](#using-the-tracing-feature)
+//! - `eprint` - prints the message to the standard error at runtime, once per call site. [Read more](#using-the-eprint-feature)
+//! - `compile_error` - emits a compile error when the condition is known at compile time, otherwise panics at runtime.
+//! - `warn` - emits a compiler warning, so it only works with the conditions that are known at compile time, a deadline warns if it's passed when you build.
+//! - `ignore` - does nothing.
 //!
-//! ## Backends
-//! and that's why we have the `chrono-backend` and the `time-backend` features, to use the `chrono` or the `time` crate to calculate the unix time stamp instead of the default implementation.
+//! every condition goes through the same actions, e.g. `log` logs a met `if` condition the same way it logs a passed deadline.
 //!
-//! I encourage you to enable one of them if you don't have a problem with adding yet another dependence to your project dependencies tree, or if you already have one of them in your dependencies already.
-//! at least until we have a better implementation for the default backend.
+//! ## Compile time `if` conditions
+//! the `if` condition is evaluated at compile time when it only consists of `cfg!(...)`, `env!`/`option_env!` comparisons and literal booleans,
+//! combined with `!`, `&&`, `||` and parentheses. so it works with the `compile-error` feature too:
+//! ```rust,ignore
+//! use todo2::todo;
 //!
-//! the backend doesn't affect the parsing part, or the syntax, it only affects the calculation of the unix time stamp, witch is internal thing, so you don't have to worry about it from this perspective.
+//! todo!("Remove this secret", if: !cfg!(debug_assertions));
+//! todo!("Drop the debug endpoint", if: option_env!("RELEASE_CHANNEL") == Some("stable") && cfg!(feature = "debug-endpoint"));
+//! let my_little_secret = "i love you";
+//! ```
+//! the supported comparisons are `env!("NAME") == "value"` (and `!=`), `option_env!("NAME") == Some("value")`, `option_env!("NAME") == None`,
+//! `option_env!("NAME").is_some()` and `option_env!("NAME").is_none()`. the environment variables are tracked, so the crate gets rebuilt when they change.
+//!
+//! any other condition, e.g. one that uses a variable, is checked at runtime like before.
 //!
-//! # Maybe?
-//! Here some ideas that I may implement in the future releases:
-//! - [ ] Implement the `with-chrono` feature, to enable the user to use the [`chrono::Utc`](https://docs.rs/chrono/latest/chrono/struct.Utc.html) or [`chrono::DateTime`](https://docs.rs/chrono/latest/chrono/struct.DateTime.html) types
-//! to specify the deadline for the `by` condition instead of the raw date.
-//! example:
+//! ## Const `if` conditions
+//! if the condition only depends on constants, you can use the `const_if` condition instead of `if`.
+//! with the `compile-error` feature, it's checked by the compiler during the const evaluation, so raising a constant breaks the build until the todo is handled:
 //! ```rust,ignore
-//! # #[macro_use]
-//! # extern crate todo2;
-//! todo!("Make a cool crate", by: chrono::Utc.with_ymd_and_hms(2024, 02, 02, 9, 0, 0));
+//! use todo2::todo;
+//!
+//! const SCHEMA_VERSION: u32 = 4;
+//!
+//! fn migrate() {
+//!     todo!("Migrate the old rows", const_if: SCHEMA_VERSION >= 4);
+//! }
 //! ```
-//! - [ ] Implement the `with-time` feature, to enable the user to use the [`time::OffsetDateTime`](https://docs.rs/time/latest/time/struct.OffsetDateTime.html) type or the [`time::macros::datetime`](https://docs.rs/time/0.3.28/time/macros/macro.datetime.html) macro to specify the deadline for the `by` condition instead of the raw date.
-//! example:
+//! the condition is put in a `const` item, so it can't use the local variables or the generic parameters.
+//! without the `compile-error` feature, it's checked at runtime like the `if` condition.
+//!
+//! ## The `version` condition
+//! the `version` condition compares the version of your crate (the `CARGO_PKG_VERSION`) with the given one, using the semver precedence rules.
+//! so a pre-release like `2.0.0-rc.1` comes before `2.0.0`, and the build metadata is ignored:
 //! ```rust,ignore
-//! # #[macro_use]
-//! # extern crate todo2;
-//! todo!("Make a cool crate", by: time::macros::datetime!(2024-02-02 09:00:00));
+//! use todo2::todo;
+//!
+//! todo!("Remove this shim", version: >= 2.0.0);
+//! todo!("Stabilize the new API", version: >= 2.0.0-rc.1);
 //! ```
-//! - [ ] Make the `if` condition parser able to evaluate some conditions at compile time, so we can use the `compile-error` feature with the `if` condition.
-//! example:
+//! the supported operators are `==`, `!=`, `>`, `>=`, `<` and `<=`, and the missing minor and patch numbers are zeros, so `>= 2` is the same as `>= 2.0.0`.
+//! it's evaluated at compile time, so it emits a compile error with the `compile-error` feature, or panics or logs an error otherwise, once the version is met.
+//!
+//! ## The `rustc` condition
+//! the `rustc` condition takes the same syntax as the `version` condition, but compares the version of the compiler that builds your crate,
+//! useful for the workarounds that a newer compiler makes unnecessary:
 //! ```rust,ignore
-//! # #[macro_use]
-//! # extern crate todo2;
-//! # fn main() {
-//!    todo!("Remove this secret", if: !cfg!(debug_assertions));
-//!    let my_little_secret = "i love you";
-//!#  }
+//! use todo2::todo;
+//!
+//! todo!("Use `std::sync::LazyLock` instead of `once_cell`", rustc: >= 1.80);
 //! ```
+//! a nightly or beta compiler is a pre-release of its version, so `rustc 1.80.0-nightly` doesn't meet `>= 1.80`.
+//! it's also evaluated at compile time, and reacts the same way as the `version` condition.
+//!
+//! ## The `dep` condition
+//! the `dep` condition checks the resolved versions of your dependencies in the `Cargo.lock`, that's found in your crate directory or in the workspace root,
+//! so you get reminded when the upgrade that fixes an upstream bug lands, or when a dependency is added or removed:
+//! ```rust,ignore
+//! use todo2::todo;
+//!
+//! todo!("Remove the workaround for serde-rs/serde#2538", dep: serde >= 1.0.200);
+//! todo!("Drop the compat layer", dep: !once_cell);
+//! todo!("Use the shared pool", dep: tokio-util);
+//! ```
+//! the version part takes the same syntax as the `version` condition, and it's met if any of the resolved versions of the dependency meets it.
+//! it's evaluated at compile time, and the lock file is tracked, so changing a dependency rebuilds the code that uses the macro.
+//! it reacts the same way as the `version` condition, so with the `compile-error` feature it's a compile error.
+//!
+//! ## The `env` condition
+//! the `env` condition checks an environment variable of the build, when the macro is expanded:
+//! ```rust,ignore
+//! use todo2::todo;
+//!
+//! todo!("Drop the debug endpoint", env: RELEASE_CHANNEL == "stable");
+//! todo!("Enable the cache", env: RELEASE_CHANNEL != "dev");
+//! todo!("Remove the CI workaround", env: CI);
+//! todo!("Make it work without the legacy config", env: !LEGACY_CONFIG);
+//! ```
+//! `env: NAME` is met when the variable is set, `env: !NAME` when it isn't, and `!=` is also met when it isn't set.
+//! the variable is tracked, so changing it rebuilds the code that uses the macro, and with the `compile-error` feature it breaks only the builds that meet the condition.
+//! otherwise it panics or logs an error at runtime, like the `version` condition.
+//!
+//! ## Relative deadlines
+//! the `in` condition takes a deadline relative to the first time that the macro is compiled, in hours (`h`), days (`d`), weeks (`w`) or months (30 days):
+//! ```rust,ignore
+//! use todo2::todo;
+//!
+//! todo!("Remove this hack", in: 30 days);
+//! todo!("Remove the cache workaround", in: 2w, id: "cache");
+//! ```
+//! the first compile time is recorded in `target/todo2-anchors` of your workspace (or in `CARGO_TARGET_DIR`), keyed by the crate, the file, the message and the optional `id`,
+//! so the debug and release builds, the features and the compiler versions share it. changing the message or the `id` starts a new deadline,
+//! and so does `cargo clean`. after that it's the same as a `by` deadline.
+//! the file is only known with rustc 1.88 or newer, so with older compilers the same message in two files shares one deadline unless they have different `id`s.
+//!
+//! to share the deadlines with your team and CI, set `TODO2_ANCHORS` to a file that you keep in the version control, a relative path starts from your `Cargo.toml`:
+//! ```toml
+//! # .cargo/config.toml
+//! [env]
+//! TODO2_ANCHORS = ".todo2-anchors"
+//! ```
+//!
+//! ## Warnings before the deadline
+//! with `warn_before`, the compiler warns you when the deadline of a `by` or `in` condition is close, so the build output and your editor start nagging before it breaks:
+//! ```rust,ignore
+//! use todo2::todo;
+//!
+//! todo!("Migrate to the new API", by: 2024-06-01, warn_before: 14 days);
+//! ```
+//! the warning has the time that's left and the message, e.g. "TODO: 9 days left before the deadline for `Migrate to the new API`".
+//! to warn before all the deadlines, set the `TODO2_WARN_BEFORE` environment variable, e.g. in your `.cargo/config.toml`:
+//! ```toml
+//! [env]
+//! TODO2_WARN_BEFORE = "2 weeks"
+//! ```
+//! the warning is a use of a deprecated function, since the stable compiler doesn't let the macros emit warnings,
+//! with the `nightly` feature and a nightly compiler it uses the `proc_macro::Diagnostic` API instead.
+//!
+//! ## Testing after the deadlines
+//! with the `test-clock` feature, the runtime checks of the deadlines use the `TODO2_NOW` variable instead of the system clock when it's set,
+//! so the tests can fast forward past them. it's a unix time stamp or an RFC 3339 date, e.g. `2026-01-01`, `2026-01-01T09:00:00Z` or `2026-01-01T09:00:00+02:00`.
+//! enable it only for the tests, so the production code doesn't read the variable,
+//! and add the `todo2-runtime` crate, the generated code calls it to read the variable, nothing of it runs without the feature:
+//! ```toml
+//! [dependencies]
+//! todo2 = "0.1.0"
+//! todo2-runtime = "0.1.0"
+//!
+//! [dev-dependencies]
+//! todo2 = { version = "0.1.0", features = ["test-clock"] }
+//! ```
+//! ```sh
+//! TODO2_NOW=2026-01-01T00:00:00Z cargo test
+//! ```
+//! it's read once per process, the first time that a `todo!` checks the clock, so set it before, e.g. at the start of the test.
+//! it doesn't change the compile time checks, e.g. the `compile-error` feature or the `warn_before` warnings, see [the clock of the build](#the-clock-of-the-build).
+//!
+//! ## The clock of the build
+//! the compile time checks, e.g. the `compile-error` feature, the `warn_before` warnings and the `in` deadlines, use the time when the macro is expanded.
+//! set `TODO2_BUILD_NOW` to override it, it's a unix time stamp or an RFC 3339 date like `TODO2_NOW`, otherwise it's [`SOURCE_DATE_EPOCH`](https://reproducible-builds.org/specs/source-date-epoch/) if it's set,
+//! so the reproducible builds produce the same artifacts:
+//! ```sh
+//! TODO2_BUILD_NOW=2030-01-01 cargo build --features compile-error
+//! SOURCE_DATE_EPOCH=$(git log -1 --format=%ct) cargo build
+//! ```
+//! cargo rebuilds the crates that have a deadline when they change.
+//!
+//! ## `no_std` support
+//! the generated code checks the deadlines with `std::time::SystemTime`, so it doesn't work in the `#![no_std]` crates.
+//! the `no-std` feature checks them only when the macro is expanded, like the `version` condition, unless you give it a clock function:
+//! a function that returns the unix time stamp as `u64`, set its path in the `TODO2_CLOCK` variable, e.g. in `.cargo/config.toml`:
+//! ```toml
+//! [env]
+//! TODO2_CLOCK = "crate::rtc::unix_time"
+//! ```
+//! ```rust,ignore
+//! #![no_std]
+//! use todo2::todo;
+//!
+//! mod rtc {
+//!     pub fn unix_time() -> u64 {
+//!         // read the real time clock
+//!     }
+//! }
+//!
+//! todo!("Calibrate the sensor", by: 2025-06-01);
+//! ```
+//! the `panic`, `log`, `tracing` and `compile_error` actions work without `std`, the `eprint` one and the `test-clock` feature don't.
+//!
+//! ## Using the `with-chrono` feature
+//! With the `with-chrono` feature, the `by` condition also takes any expression that evaluates to a [`chrono::DateTime`](https://docs.rs/chrono/latest/chrono/struct.DateTime.html),
+//! or to the [`chrono::LocalResult`](https://docs.rs/chrono/latest/chrono/offset/enum.LocalResult.html) that the `with_ymd_and_hms` and friends return, in any time zone.
+//! you also need to have the `chrono` crate in your dependencies:
+//! ```rust,ignore
+//! use chrono::TimeZone;
+//! use todo2::todo;
+//!
+//! todo!("Make a cool crate", by: chrono::Utc.with_ymd_and_hms(2024, 2, 2, 9, 0, 0));
+//! let deadline = chrono::FixedOffset::east_opt(2 * 3600).unwrap().with_ymd_and_hms(2024, 2, 2, 9, 0, 0).unwrap();
+//! todo!("Make a cool crate", by: deadline);
+//! ```
+//! the expression is evaluated at runtime and compared with `chrono::Utc::now()`, so the `compile-error` feature doesn't apply to it, and it panics or logs an error instead.
+//! the raw date syntax keeps working next to it.
+//!
+//! ## Using the `with-time` feature
+//! With the `with-time` feature, the `by` condition also takes any expression that evaluates to a [`time::OffsetDateTime`](https://docs.rs/time/latest/time/struct.OffsetDateTime.html),
+//! or to a [`time::PrimitiveDateTime`](https://docs.rs/time/latest/time/struct.PrimitiveDateTime.html) which is assumed to be in UTC.
+//! you also need to have the `time` crate in your dependencies, with the `macros` feature if you want to use the `datetime!` macro:
+//! ```rust,ignore
+//! use todo2::todo;
+//!
+//! todo!("Make a cool crate", by: time::macros::datetime!(2024-02-02 09:00 UTC));
+//! let deadline = time::OffsetDateTime::now_utc() + time::Duration::weeks(2);
+//! todo!("Make a cool crate", by: deadline);
+//! ```
+//! when the deadline is a `datetime!` invocation, the macro evaluates it at compile time, so the `compile-error` feature works with it like with the raw date.
+//! if it uses a syntax that our parser doesn't understand yet, e.g. the fractions of a second, or if it's any other expression, it's compared at runtime with `time::OffsetDateTime::now_utc()`.
+//!
+//! # Time in the `by` condition
+//! by default, the `by` condition takes a raw date and parse it with our custom parser, which expects the date in the `YYYY-MM-DD` format and `YYYY-MM-DD at HH:MM` or `YYYY-MM-DD @ HH:MM` format if you have the `and-time` feature enabled.
+//! and then it calculates the unix time stamp in UTC, and then compares it with the current time stamp.
+//! the date is checked against the calendar, so a typo is a compile error that tells you what is wrong, e.g. `by: 2023-02-29` fails with ``Invalid day `29`, February 2023 has 28 days``.
+//!
+//! the date can also be written the way you plan, they're at the start of the period like `by: 2025`:
+//! - `YYYY-Qn` for a quarter, e.g. `by: 2025-Q3` is July 1st
+//! - `YYYY-Wnn` or `YYYY-Wnn-D` for an ISO week and its day from 1 (Monday) to 7 (Sunday), e.g. `by: 2025-W12` is Monday March 17th and `by: 2025-W12-5` is Friday.
+//!   the first week of the year is the one with January 4th, so `2025-W01` starts on December 30th 2024, and some years have 53 weeks
+//! - an English month name or its first three letters, e.g. `by: March 2025` or `by: 15 Mar 2025`
+//!
+//! the time can be:
+//! - `HH`, `HH:MM` or `HH:MM:SS` in the 24-hour clock, e.g. `by: 2025-01-31 at 17:30:15`
+//! - the same with `am` or `pm` after it for the 12-hour clock, e.g. `by: 2025-01-31 at 5:30pm` or `by: 2025-01-31 at 9 am`, `12am` is the midnight and `12pm` is the noon
+//! - `noon` for 12:00, `midnight` for 00:00 at the start of the day, or `eod` for 23:59:59 at the end of the day, e.g. `by: 2025-01-31 at eod`
+//!
+//! you can also put an offset after the date or the time, to tell in which time zone the deadline is written:
+//! - `+HH:MM`, `+HHMM` or `+HH` (and the same with `-`) for a fixed offset, e.g. `by: 2025-03-01 at 9:00 +02:00`,
+//!   a `-HH` offset after a date without the day needs a space before it, e.g. `by: 2025-03 -05`, the compiler tells the spaces since rustc 1.88, so write `-05:00` for the older ones
+//! - `Z` or `UTC` for UTC, which is the same as not specifying the offset at all, e.g. `by: 2025-03-01 at 9:00 Z`
+//! - `local` for the time zone of the machine that compiles the code, e.g. `by: 2025-03-01 at 9:00 local`, if you have the `local-tz` feature or the `chrono-backend` enabled.
+//!   it's resolved through the `TZ` environment variable or the system time zone, with the daylight saving time rules of that date, and changing `TZ` rebuilds the code.
+//!   a time that doesn't exist because the clocks go forward is shifted forward, and a time that happens twice because the clocks go back takes the later one.
+//! - `in Area/Location` for an IANA time zone name, e.g. `by: 2025-06-01 at 17:00 in Europe/Berlin`, if you have the `tz` feature enabled, or the `chrono-tz` feature with the `chrono-backend`.
+//!   unlike `local`, a time that doesn't exist or happens twice because of the daylight saving time is a compile error that tells you the possible offsets.
+//!
+//! the `by` condition also takes the deadline as a string in the ISO 8601 format that RFC 3339 uses, e.g. when you copy it from a ticket or a calendar export:
+//! ```rust,ignore
+//! todo!("Migrate the billing", by: "2025-03-26T09:00:00+02:00");
+//! todo!("Migrate the billing", by: "2025-03-26");
+//! ```
+//! it's `YYYY-MM-DD`, `YYYY-MM` or `YYYY`, optionally followed by `T` or a space and the time `HH:MM` or `HH:MM:SS`, then `Z` or an offset like `+02:00`.
+//! the time without an offset is in UTC, and the fractions of a second are ignored. it doesn't need the `and-time` feature, and its errors point at the string.
+//!
+//! this for the parsing part, noting interested here. just macros magic. the complexity comes when we want to calculate the unix time stamp from the parsed date.
+//! here the time zones and the daylight saving time and the leap seconds come to play.
+//! and I don't want to deal with this complexity 'cause I'm lazy and this is a "proc macro" not a normal crate witch means that it runs at compile time, and we all know that the rust compile times is so "fast" :) and I don't want to make it slower.
+//! so the default backend calculates the unix time stamp with the days from civil algorithm, see [Howard Hinnant's date algorithms](http://howardhinnant.github.io/date_algorithms.html#days_from_civil).
+//! it's exact for any date in the gregorian calendar, with the leap years, and it doesn't pull a date crate into your builds, unlike the backends below. the dates before 1970 work too,
+//! as long as the deadline itself isn't before the unix epoch after its offset, e.g. `by: 1969-12-31 at 23:00 -02:00` is `1970-01-01T01:00:00Z`.
+//!
+//! ## Backends
+//! the `chrono-backend` and the `time-backend` features use the `chrono` or the `time` crate to calculate the unix time stamp instead of the default implementation.
+//! they used to be more accurate than the default one, now the tests check that the three of them agree on every day from 1600 to 2400, so you don't need them.
+//! they're still here for the builds that enable them, and they pull their crate into the proc macro build.
+//!
+//! the backend doesn't affect the parsing part, or the syntax, it only affects the calculation of the unix time stamp, witch is internal thing, so you don't have to worry about it from this perspective.
 //!
 //! # Contributing
 //! I'm happy to accept any contributions, just consider reading the [CONTRIBUTING.md](https://github.com/0x61nas/todo2/blob/aurora/CONTRIBUTING.md) guide first. to avoid waste waste our time on some unnecessary things.
@@ -153,34 +426,124 @@
 //! This project is licensed under the MIT license. [Read more](https://github.com/0x61nas/todo2/blob/aurora/LICENSE)
 //! And you can use it under the Unlicense license if you want. [Read more](https://github.com/0x61nas/todo2/blob/aurora/LICENSE-UNLICENSE)
 //!
+#![cfg_attr(feature = "nightly", feature(proc_macro_diagnostic))]
 
 #[cfg(all(feature = "chrono-backend", feature = "time-backend"))]
 compile_error!("You can only use one backend at a time");
 #[cfg(all(feature = "log", feature = "compile-error"))]
 compile_error!("You can only use one of the `log` or the `compile-error` features at a time");
+#[cfg(all(feature = "tracing", any(feature = "log", feature = "compile-error")))]
+compile_error!(
+    "You can't use the `tracing` feature with the `log` or the `compile-error` features"
+);
+#[cfg(all(
+    feature = "eprint",
+    any(feature = "log", feature = "tracing", feature = "compile-error")
+))]
+compile_error!(
+    "You can't use the `eprint` feature with the `log`, the `tracing` or the `compile-error` features"
+);
+#[cfg(all(feature = "chrono-backend", feature = "tz"))]
+compile_error!("The `chrono-backend` resolves the time zone names with `chrono-tz`, enable the `chrono-tz` feature instead of `tz`");
+#[cfg(all(feature = "no-std", feature = "test-clock"))]
+compile_error!("The `test-clock` feature reads `TODO2_NOW` with `std`, you can't use it with the `no-std` feature");
 
+mod action;
+mod anchor;
+mod clock;
 mod date;
+mod dep;
+mod duration;
+mod env_cond;
 mod if_cond;
+mod version;
 
 extern crate proc_macro;
 
-use crate::date::parse_date;
-use crate::if_cond::parse_if;
+use crate::action::{parse_action, tracing_warning, warning, Action, Check, Kind, Metadata, Todo};
+use crate::anchor::{anchor, call_site_file, ANCHORS_ENV};
+use crate::clock::{
+    compile_time_now, runtime_now, BUILD_NOW_ENV, CLOCK_ENV, SOURCE_DATE_EPOCH_ENV,
+};
+#[cfg(feature = "with-time")]
+use crate::date::eval_datetime_macro;
+#[cfg(any(feature = "with-chrono", feature = "with-time"))]
+use crate::date::{is_date_expr, parse_date_expr};
+use crate::date::{is_local_date, parse_date, parse_date_str};
+use crate::dep::{find_lockfile, parse_dep, read_lockfile, DepCond};
+use crate::duration::{parse_duration, parse_duration_str, time_left};
+use crate::env_cond::{parse_env, str_lit, EnvCond};
+use crate::if_cond::{eval_if, parse_if, StaticIf};
+use crate::version::{parse_version, pkg_version, rustc_version, VersionCond};
 use proc_macro::TokenStream;
 use proc_macro2::token_stream::IntoIter;
-use proc_macro2::TokenTree;
-use quote::{quote, TokenStreamExt};
+use proc_macro2::{Span, TokenTree};
+use quote::{quote, quote_spanned, TokenStreamExt};
 use std::iter::Peekable;
 
 /// An alias for `Result<T, String>`
 pub(crate) type Result<T> = std::result::Result<T, String>;
 
+/// Returns the directory of the crate that's being compiled
+pub(crate) fn manifest_dir() -> Result<std::path::PathBuf> {
+    std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(std::path::PathBuf::from)
+        .ok_or_else(|| "Can't find the crate directory, are you using cargo?".to_string())
+}
+
+/// Runs one of the condition parsers on the tokens of a test, e.g. `parse_tokens(parse_version, quote!(>= 1.0))`
+#[cfg(test)]
+pub(crate) fn parse_tokens<T>(
+    parse: fn(&mut Peekable<IntoIter>) -> Result<T>,
+    tokens: proc_macro2::TokenStream,
+) -> Result<T> {
+    parse(&mut tokens.into_iter().peekable())
+}
+
+/// An error that points at the token that caused it, e.g. a date string, instead of the whole macro call
+pub(crate) struct SpannedError {
+    pub(crate) message: String,
+    pub(crate) span: Span,
+}
+
+impl From<String> for SpannedError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            span: Span::call_site(),
+        }
+    }
+}
+
+impl SpannedError {
+    fn to_compile_error(&self) -> TokenStream {
+        let message = &self.message;
+        TokenStream::from(quote_spanned!(self.span=> compile_error!(#message)))
+    }
+}
+
 /// The condition type variant
 enum ConditionTyp {
     /// takes the date in the unix time format
     By(u64),
+    /// takes the deadline as an expression that evaluates to a `chrono::DateTime` or a `time::OffsetDateTime`
+    #[cfg(any(feature = "with-chrono", feature = "with-time"))]
+    ByExpr(proc_macro2::TokenStream),
     /// takes the condition as a token stream
     If(proc_macro2::TokenStream),
+    /// takes a const-evaluable condition as a token stream
+    ConstIf(proc_macro2::TokenStream),
+    /// takes the condition on the crate version
+    Version(VersionCond),
+    /// takes the condition on the compiler version
+    Rustc(VersionCond),
+    /// takes the condition on a dependency in the `Cargo.lock`
+    Dep(DepCond),
+    /// takes the condition on an environment variable of the build
+    Env(EnvCond),
+    /// takes the deadline relative to the first expansion in seconds, and the optional id of the call site,
+    /// it's resolved to [`ConditionTyp::By`] before generating the code
+    In { duration: u64, id: Option<String> },
 }
 
 /// Indicates unfinished implementation or the the intention to do something in the future or when the condition is met
@@ -205,8 +568,8 @@ enum ConditionTyp {
 /// todo!("Read the API key from the environment variable", by: 2023-01-01 at 9:00);
 /// ```
 ///
-/// This will panic if the condition is met, unfortunately, the `compile-error` feature doesn't change the behavior of `if` condition,
-/// because we can't guarantee that all values will be known at compile time.
+/// This will panic if the condition is met, the `compile-error` feature or `action: compile_error` only changes the behavior of the `if` condition
+/// when it can be evaluated at compile time, e.g. `if: !cfg!(debug_assertions)`, because we can't guarantee that all values will be known at compile time.
 /// ```rust,should_panic
 /// #  use todo2::todo;
 /// let username = "The Hacker";
@@ -260,89 +623,253 @@ pub fn todo(tokens: TokenStream) -> TokenStream {
             "Expected `,` or `;` after the massage"
         )));
     }
-    let conditions = match parse_conditions(tokens) {
+    let (conditions, options) = match parse_conditions(tokens) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error(),
+    };
+    let relative = conditions
+        .iter()
+        .any(|c| matches!(c, ConditionTyp::In { .. }));
+    let dated = relative || conditions.iter().any(|c| matches!(c, ConditionTyp::By(_)));
+    // The build clock is only read for the deadlines, an invalid `TODO2_BUILD_NOW` doesn't break the other conditions
+    let now = if dated {
+        match compile_time_now() {
+            Ok(now) => now,
+            Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+        }
+    } else {
+        0
+    };
+    let conditions = match resolve_relative(conditions, &msg, now) {
         Ok(conditions) => conditions,
         Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
     };
+    let warn_before = match options.warn_before.map_or_else(default_warn_before, Ok) {
+        Ok(warn_before) => warn_before,
+        Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+    };
+
+    let action = options.action.unwrap_or_else(Action::from_features);
+    // The runtime clock is only read for the deadlines, so `TODO2_CLOCK` doesn't matter to the other conditions
+    #[cfg(any(feature = "with-chrono", feature = "with-time"))]
+    let dated = dated
+        || conditions
+            .iter()
+            .any(|c| matches!(c, ConditionTyp::ByExpr(_)));
+    let runtime_now = if dated {
+        match runtime_now() {
+            Ok(runtime_now) => runtime_now,
+            Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+        }
+    } else {
+        None
+    };
 
     let mut rt = quote!();
+    if relative {
+        rt.append_all(quote!(let _ = ::core::option_env!(#ANCHORS_ENV);));
+    }
+    if options.local {
+        rt.append_all(quote!(let _ = ::core::option_env!("TZ");));
+    }
     for condition in conditions {
-        match condition {
+        let (check, text, kind) = match condition {
             ConditionTyp::By(time) => {
-                let msg = format!("TODO: The deadline for `{}` has passed, do it now!", msg);
-                #[cfg(feature = "compile-error")]
-                {
-                    let ct = std::time::SystemTime::now()
-                        .duration_since(std::time::SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-
-                    if time <= ct {
-                        return TokenStream::from(quote!(compile_error!(#msg)));
-                    }
+                rt.append_all(quote!(
+                    let _ = ::core::option_env!(#WARN_BEFORE_ENV);
+                    let _ = ::core::option_env!(#BUILD_NOW_ENV);
+                    let _ = ::core::option_env!(#SOURCE_DATE_EPOCH_ENV);
+                ));
+                if time > now && time - now <= warn_before && action != Action::Ignore {
+                    let note = format!(
+                        "TODO: {} left before the deadline for `{}`",
+                        time_left(time - now),
+                        msg
+                    );
+                    rt.append_all(warning(&note));
                 }
-                // TODO: consider `no_std` compatibility?
-                #[cfg(not(any(feature = "with-chrono", feature = "with-time")))]
-                {
-                    if cfg!(feature = "log") {
-                        rt.append_all(quote! {
-                            if #time <= ::std::time::SystemTime::now().duration_since(::std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs() {
-                                ::log::error!(#msg);
-                            }
-                        });
-                    } else {
-                        rt.append_all(quote! {
-                            if #time <= ::std::time::SystemTime::now().duration_since(::std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs() {
-                                ::core::panic!(#msg);
-                            }
-                        });
-                    }
+                let kind = Kind::By(Some(time));
+                if cfg!(feature = "no-std") {
+                    rt.append_all(quote!(let _ = ::core::option_env!(#CLOCK_ENV);));
                 }
+                let check = match &runtime_now {
+                    Some(runtime_now) => {
+                        if action == Action::Tracing && warn_before > 0 {
+                            let start = time.saturating_sub(warn_before);
+                            let note =
+                                format!("TODO: The deadline for `{}` is close, do it soon!", msg);
+                            rt.append_all(tracing_warning(
+                                quote!((#start..#time).contains(&#runtime_now)),
+                                &note,
+                                &Todo {
+                                    message: &msg,
+                                    kind,
+                                    meta: &options.meta,
+                                },
+                            ));
+                        }
+                        Check::Runtime {
+                            cond: quote!(#time <= #runtime_now),
+                            met_at_build: Some(time <= now),
+                        }
+                    }
+                    // Without a clock, the deadline is only checked when the macro is expanded
+                    None if time <= now => Check::Met,
+                    None => continue,
+                };
+                let msg = format!("TODO: The deadline for `{}` has passed, do it now!", msg);
+                (check, msg, kind)
+            }
+            #[cfg(any(feature = "with-chrono", feature = "with-time"))]
+            ConditionTyp::ByExpr(deadline) => {
+                let mut impls = quote!();
                 #[cfg(feature = "with-chrono")]
                 {
-                    if cfg!(feature = "log") {
-                        rt.append_all(quote! {
-                            if #time <= ::chrono::Utc::now().timestamp() as u64 {
-                                ::log::error!(#msg);
+                    let invalid = format!("TODO: The deadline for `{}` doesn't exist", msg);
+                    impls.append_all(quote! {
+                        impl<Tz: ::chrono::TimeZone> Todo2Deadline for ::chrono::DateTime<Tz> {
+                            fn todo2_timestamp(&self) -> i64 {
+                                self.timestamp()
                             }
-                        });
-                    } else {
-                        rt.append_all(quote! {
-                            if #time <= ::chrono::Utc::now().timestamp() as u64 {
-                                ::core::panic!(#msg);
+                        }
+                        impl<Tz: ::chrono::TimeZone> Todo2Deadline for ::chrono::LocalResult<::chrono::DateTime<Tz>> {
+                            fn todo2_timestamp(&self) -> i64 {
+                                match self {
+                                    ::chrono::LocalResult::Single(deadline) => deadline.timestamp(),
+                                    ::chrono::LocalResult::Ambiguous(earliest, _) => earliest.timestamp(),
+                                    ::chrono::LocalResult::None => ::core::panic!("{}", #invalid),
+                                }
                             }
-                        });
-                    }
+                        }
+                    });
                 }
                 #[cfg(feature = "with-time")]
-                {
-                    if cfg!(feature = "log") {
-                        rt.append_all(quote! {
-                            if #time <= ::time::OffsetDateTime::now_utc().unix_timestamp() as u64 {
-                                ::log::error!(#msg);
-                            }
-                        });
-                    } else {
-                        rt.append_all(quote! {
-                            if #time <= ::time::OffsetDateTime::now_utc().unix_timestamp() as u64 {
-                                ::core::panic!(#msg);
-                            }
-                        });
+                impls.append_all(quote! {
+                    impl Todo2Deadline for ::time::OffsetDateTime {
+                        fn todo2_timestamp(&self) -> i64 {
+                            self.unix_timestamp()
+                        }
                     }
-                }
+                    impl Todo2Deadline for ::time::PrimitiveDateTime {
+                        fn todo2_timestamp(&self) -> i64 {
+                            self.assume_utc().unix_timestamp()
+                        }
+                    }
+                });
+                // The same clock as the other deadlines, so `TODO2_NOW` and `TODO2_CLOCK` work for them too
+                let now = match &runtime_now {
+                    Some(now) => quote!((#now as i64)),
+                    None if cfg!(feature = "with-chrono") => {
+                        quote!(::chrono::Utc::now().timestamp())
+                    }
+                    None => quote!(::time::OffsetDateTime::now_utc().unix_timestamp()),
+                };
+                // The expression can't be evaluated at compile time, so it's always checked at runtime
+                let check = Check::Runtime {
+                    cond: quote! {{
+                        trait Todo2Deadline {
+                            fn todo2_timestamp(&self) -> i64;
+                        }
+                        #impls
+                        Todo2Deadline::todo2_timestamp(&(#deadline)) <= #now
+                    }},
+                    met_at_build: None,
+                };
+                let msg = format!("TODO: The deadline for `{}` has passed, do it now!", msg);
+                (check, msg, Kind::By(None))
             }
             ConditionTyp::If(if_cond) => {
-                let msg = format!("TODO: {}", msg);
-                rt.append_all(quote! {
-                    if #if_cond {
-                        ::core::panic!(#msg);
+                let check = match eval_if(&if_cond) {
+                    Ok(Some(StaticIf { predicate, env })) => {
+                        // `option_env!` makes the compiler track the variables, so it rebuilds when they change
+                        rt.append_all(quote!(#(let _ = ::core::option_env!(#env);)*));
+                        Check::Cfg(predicate)
                     }
-                });
+                    Ok(None) => Check::Runtime {
+                        cond: if_cond,
+                        met_at_build: None,
+                    },
+                    Err(e) => return e.to_compile_error(),
+                };
+                (check, format!("TODO: {}", msg), Kind::If)
             }
-        }
+            ConditionTyp::ConstIf(cond) => {
+                (Check::Const(cond), format!("TODO: {}", msg), Kind::ConstIf)
+            }
+            ConditionTyp::Version(cond) => {
+                let version = match pkg_version() {
+                    Ok(version) => version,
+                    Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+                };
+                if !cond.is_met(&version) {
+                    continue;
+                }
+                let msg = format!(
+                    "TODO: The crate version `{}` meets `{}`, do `{}` now!",
+                    version, cond, msg
+                );
+                (Check::Met, msg, Kind::Version)
+            }
+            ConditionTyp::Rustc(cond) => {
+                let version = match rustc_version() {
+                    Ok(version) => version,
+                    Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+                };
+                if !cond.is_met(&version) {
+                    continue;
+                }
+                let msg = format!(
+                    "TODO: The compiler version `{}` meets `{}`, do `{}` now!",
+                    version, cond, msg
+                );
+                (Check::Met, msg, Kind::Rustc)
+            }
+            ConditionTyp::Dep(cond) => {
+                let packages =
+                    match find_lockfile().and_then(|path| Ok((read_lockfile(&path)?, path))) {
+                        Ok((packages, path)) => {
+                            // Makes the compiler track the lock file, to rebuild when a dependency changes
+                            let path = path.to_string_lossy();
+                            rt.append_all(quote!(
+                                const _: &[u8] = ::core::include_bytes!(#path);
+                            ));
+                            packages
+                        }
+                        Err(e) => return TokenStream::from(quote!(compile_error!(#e))),
+                    };
+                let Some(reason) = cond.check(&packages) else {
+                    continue;
+                };
+                (
+                    Check::Met,
+                    format!("TODO: {}, do `{}` now!", reason, msg),
+                    Kind::Dep,
+                )
+            }
+            ConditionTyp::Env(cond) => {
+                let name = &cond.name;
+                rt.append_all(quote!(let _ = ::core::option_env!(#name);));
+                let Some(reason) = cond.eval() else {
+                    continue;
+                };
+                (
+                    Check::Met,
+                    format!("TODO: {}, do `{}` now!", reason, msg),
+                    Kind::Env,
+                )
+            }
+            ConditionTyp::In { .. } => unreachable!("The relative deadlines are already resolved"),
+        };
+        let todo = Todo {
+            message: &msg,
+            kind,
+            meta: &options.meta,
+        };
+        rt.append_all(action.emit(check, &text, &todo));
     }
 
-    TokenStream::from(rt)
+    // One block, so the statements above are valid where an expression is expected, e.g. in a match arm
+    TokenStream::from(quote!({ #rt }))
 }
 
 fn parse_msg(tokens: &mut Peekable<IntoIter>) -> Result<Option<String>> {
@@ -359,35 +886,269 @@ fn parse_msg(tokens: &mut Peekable<IntoIter>) -> Result<Option<String>> {
     }
 }
 
-fn parse_conditions(mut tokens: Peekable<IntoIter>) -> Result<Vec<ConditionTyp>> {
+/// The options that apply to all the conditions of the macro call
+#[derive(Default)]
+struct Options {
+    /// How long before the deadline to start warning, in seconds
+    warn_before: Option<u64>,
+    /// Overrides the crate-wide action
+    action: Option<Action>,
+    /// The owner and the ticket of the TODO
+    meta: Metadata,
+    /// A deadline is in the build machine time zone, so it changes with the `TZ` variable
+    local: bool,
+}
+
+/// The keys of the conditions and the options, in the order of the docs
+const KEYS: &[&str] = &[
+    "by",
+    "if",
+    "const_if",
+    "version",
+    "rustc",
+    "dep",
+    "env",
+    "in",
+    "id",
+    "warn_before",
+    "action",
+    "owner",
+    "ticket",
+];
+
+/// The error for a token where a key is expected, it lists all the keys
+fn unknown_key(token: &TokenTree) -> SpannedError {
+    let keys = KEYS
+        .iter()
+        .map(|key| format!("`{}`", key))
+        .collect::<Vec<_>>();
+    let (last, keys) = keys.split_last().expect("There are keys");
+    SpannedError {
+        message: format!(
+            "Expected {} or {}, found `{}`",
+            keys.join(", "),
+            last,
+            token
+        ),
+        span: token.span(),
+    }
+}
+
+fn parse_conditions(
+    mut tokens: Peekable<IntoIter>,
+) -> std::result::Result<(Vec<ConditionTyp>, Options), SpannedError> {
     let mut conditions = Vec::with_capacity(2);
+    let mut options = Options::default();
+    let mut id = None;
     while let Some(token) = tokens.next() {
         match token {
             TokenTree::Ident(ident) => {
                 let Some(nt) = tokens.next() else {
-                    return Err(format!("Expected `:` after `{}`", ident));
+                    return Err(format!("Expected `:` after `{}`", ident).into());
                 };
                 let TokenTree::Punct(punct) = nt else {
-                    return Err(format!("Expected `:` after `{}` got `{}`", ident, nt));
+                    return Err(format!("Expected `:` after `{}` got `{}`", ident, nt).into());
                 };
                 if punct.as_char() != ':' {
-                    return Err(format!("Expected `:` after `{}` got `{}`", ident, punct));
+                    return Err(format!("Expected `:` after `{}` got `{}`", ident, punct).into());
                 }
                 match ident.to_string().as_str() {
-                    "by" => conditions.push(ConditionTyp::By(parse_date(&mut tokens)?)),
+                    #[cfg(any(feature = "with-chrono", feature = "with-time"))]
+                    "by" if is_date_expr(&mut tokens) => {
+                        let deadline = parse_date_expr(&mut tokens)?;
+                        #[cfg(feature = "with-time")]
+                        if let Some(time) = eval_datetime_macro(&deadline)? {
+                            conditions.push(ConditionTyp::By(time));
+                            continue;
+                        }
+                        conditions.push(ConditionTyp::ByExpr(deadline))
+                    }
+                    "by" => {
+                        options.local |= is_local_date(&tokens);
+                        conditions.push(ConditionTyp::By(parse_by(&mut tokens)?))
+                    }
                     "if" => conditions.push(ConditionTyp::If(parse_if(&mut tokens)?)),
-                    _ => return Err("Expected `by` or `if`".to_string()),
+                    "const_if" => conditions.push(ConditionTyp::ConstIf(parse_if(&mut tokens)?)),
+                    "version" => {
+                        conditions.push(ConditionTyp::Version(parse_version(&mut tokens)?))
+                    }
+                    "rustc" => conditions.push(ConditionTyp::Rustc(parse_version(&mut tokens)?)),
+                    "dep" => conditions.push(ConditionTyp::Dep(parse_dep(&mut tokens)?)),
+                    "env" => conditions.push(ConditionTyp::Env(parse_env(&mut tokens)?)),
+                    "in" => conditions.push(ConditionTyp::In {
+                        duration: parse_duration(&mut tokens)?,
+                        id: None,
+                    }),
+                    "id" => id = Some(parse_str(&mut tokens, "id")?),
+                    "warn_before" => options.warn_before = Some(parse_duration(&mut tokens)?),
+                    "action" => options.action = Some(parse_action(&mut tokens)?),
+                    "owner" => options.meta.owner = Some(parse_str(&mut tokens, "owner")?),
+                    "ticket" => options.meta.ticket = Some(parse_str(&mut tokens, "ticket")?),
+                    _ => return Err(unknown_key(&TokenTree::Ident(ident))),
                 }
             }
             TokenTree::Punct(punct) => {
                 let punct = punct.as_char();
-                if (punct == ',' || punct == ';') && !conditions.is_empty() {
+                if (punct == ',' || punct == ';')
+                    && (!conditions.is_empty()
+                        || id.is_some()
+                        || options.warn_before.is_some()
+                        || options.action.is_some()
+                        || options.meta != Metadata::default())
+                {
                     continue;
                 }
-                return Err(format!("Unexpected `{punct}`"));
+                return Err(format!("Unexpected `{punct}`").into());
             }
-            _ => return Err("Expected `by` or `if`".to_string()),
+            token => return Err(unknown_key(&token)),
+        }
+    }
+    if let Some(id) = id {
+        let mut relative = conditions
+            .iter_mut()
+            .filter_map(|c| match c {
+                ConditionTyp::In { id, .. } => Some(id),
+                _ => None,
+            })
+            .peekable();
+        if relative.peek().is_none() {
+            return Err("The `id` is only used with the `in` condition"
+                .to_string()
+                .into());
         }
+        relative.for_each(|c| *c = Some(id.clone()));
+    }
+    let has_deadline = conditions
+        .iter()
+        .any(|c| matches!(c, ConditionTyp::By(_) | ConditionTyp::In { .. }));
+    if options.warn_before.is_some() && !has_deadline {
+        return Err(
+            "The `warn_before` is only used with the `by` and `in` conditions"
+                .to_string()
+                .into(),
+        );
+    }
+    Ok((conditions, options))
+}
+
+/// Parses the raw date of the `by` condition, or the date string with its errors at the string
+fn parse_by(tokens: &mut Peekable<IntoIter>) -> std::result::Result<u64, SpannedError> {
+    let Some(TokenTree::Literal(lit)) = tokens.peek() else {
+        return Ok(parse_date(tokens)?);
+    };
+    let Ok(date) = str_lit(&lit.to_string()) else {
+        return Ok(parse_date(tokens)?);
+    };
+    let span = lit.span();
+    let _ = tokens.next();
+    let time = parse_date_str(&date).map_err(|message| SpannedError { message, span })?;
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ',' || punct.as_char() == ';' => {}
+        None => {}
+        Some(nt) => {
+            return Err(SpannedError {
+                message: format!("Unexpected `{}` after the date string", nt),
+                span: nt.span(),
+            })
+        }
+    }
+    Ok(time)
+}
+
+/// Parses the string value of a key, e.g. `owner: "alice"`, with the errors at the value
+fn parse_str(
+    tokens: &mut Peekable<IntoIter>,
+    key: &str,
+) -> std::result::Result<String, SpannedError> {
+    match tokens.next() {
+        Some(TokenTree::Literal(lit)) => {
+            str_lit(&lit.to_string()).map_err(|message| SpannedError {
+                message,
+                span: lit.span(),
+            })
+        }
+        Some(nt) => Err(SpannedError {
+            message: format!("Expected a string after `{}:`, found `{}`", key, nt),
+            span: nt.span(),
+        }),
+        None => Err(format!("Expected a string after `{}:`", key).into()),
+    }
+}
+
+/// Resolves the relative deadlines to absolute ones, starting from when the call site was first expanded
+fn resolve_relative(
+    conditions: Vec<ConditionTyp>,
+    msg: &str,
+    now: u64,
+) -> Result<Vec<ConditionTyp>> {
+    conditions
+        .into_iter()
+        .map(|condition| match condition {
+            ConditionTyp::In { duration, id } => {
+                let anchor = anchor(&call_site_file(), msg, id.as_deref(), now)?;
+                Ok(ConditionTyp::By(anchor.saturating_add(duration)))
+            }
+            condition => Ok(condition),
+        })
+        .collect()
+}
+
+/// The environment variable that sets the default of `warn_before`
+const WARN_BEFORE_ENV: &str = "TODO2_WARN_BEFORE";
+
+/// Reads the default of `warn_before` from the environment, it's zero if it isn't set
+fn default_warn_before() -> Result<u64> {
+    match std::env::var(WARN_BEFORE_ENV) {
+        Ok(duration) => parse_duration_str(&duration)
+            .map_err(|e| format!("{} in the `{}` variable", e, WARN_BEFORE_ENV)),
+        Err(_) => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions_error(tokens: proc_macro2::TokenStream) -> String {
+        match parse_conditions(tokens.into_iter().peekable()) {
+            Ok(_) => panic!("Expected an error"),
+            Err(err) => err.message,
+        }
+    }
+
+    #[test]
+    fn test_parse_id() {
+        let (conditions, _) = parse_conditions(quote!(in: 2w, id: "cache").into_iter().peekable())
+            .unwrap_or_else(|err| panic!("{}", err.message));
+        assert!(
+            matches!(&conditions[..], [ConditionTyp::In { id: Some(id), .. }] if id == "cache")
+        );
+
+        assert_eq!(
+            conditions_error(quote!(in: 2w, id: 42)),
+            "Expected a plain string, found `42`"
+        );
+        assert_eq!(
+            conditions_error(quote!(in: 2w, id: r"cache")),
+            "Expected a plain string, found `r\"cache\"`"
+        );
+        assert_eq!(
+            conditions_error(quote!(in: 2w, id: cache)),
+            "Expected a string after `id:`, found `cache`"
+        );
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let expected = "Expected `by`, `if`, `const_if`, `version`, `rustc`, `dep`, `env`, `in`, \
+            `id`, `warn_before`, `action`, `owner` or `ticket`, found";
+        assert_eq!(
+            conditions_error(quote!(deadline: 2099-01-01)),
+            format!("{} `deadline`", expected)
+        );
+        assert_eq!(
+            conditions_error(quote!(by: 2099-01-01, "later")),
+            format!("{} `\"later\"`", expected)
+        );
     }
-    Ok(conditions)
 }
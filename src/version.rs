@@ -0,0 +1,250 @@
+use crate::Result;
+use proc_macro2::token_stream::IntoIter;
+use proc_macro2::TokenTree;
+use semver::Version;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+/// The comparison operator of a version condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// A version condition, e.g. `>= 2.0.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VersionCond {
+    pub(crate) op: Op,
+    pub(crate) version: Version,
+}
+
+impl VersionCond {
+    /// Checks if the given version meets the condition, using the semver precedence (the build metadata is ignored)
+    pub(crate) fn is_met(&self, version: &Version) -> bool {
+        let ord = version.cmp_precedence(&self.version);
+        match self.op {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Ne => ord != Ordering::Equal,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Ge => ord != Ordering::Less,
+            Op::Lt => ord == Ordering::Less,
+            Op::Le => ord != Ordering::Greater,
+        }
+    }
+}
+
+impl std::fmt::Display for VersionCond {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        };
+        write!(f, "{} {}", op, self.version)
+    }
+}
+
+/// Parses a version condition in the `OP VERSION` format, e.g. `>= 2.0.0` or `< 1.0.0-rc.1`.
+///
+/// The missing minor and patch numbers are zeros, so `>= 2` is the same as `>= 2.0.0`.
+pub(crate) fn parse_version(tokens: &mut Peekable<IntoIter>) -> Result<VersionCond> {
+    let mut op = String::new();
+    while let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        let punct = punct.as_char();
+        if !matches!(punct, '<' | '>' | '=' | '!') {
+            break;
+        }
+        op.push(punct);
+        let _ = tokens.next();
+    }
+    let op = match op.as_str() {
+        "==" | "=" => Op::Eq,
+        "!=" => Op::Ne,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        "" => {
+            return Err("Expected a comparison operator before the version, e.g. `>=`".to_string())
+        }
+        _ => return Err(format!("Unknown comparison operator `{}`", op)),
+    };
+
+    // `2.0.0-rc.1` is tokenized as `2.0`, `.`, `0`, `-`, `rc`, `.` and `1`
+    let mut version = String::new();
+    for nt in tokens.by_ref() {
+        if let TokenTree::Punct(punct) = &nt {
+            if punct.as_char() == ',' || punct.as_char() == ';' {
+                break;
+            }
+        }
+        version.push_str(&nt.to_string());
+    }
+    if version.is_empty() {
+        return Err("Expected a version after the comparison operator".to_string());
+    }
+    Ok(VersionCond {
+        op,
+        version: parse_semver(&version)?,
+    })
+}
+
+/// Parses a semver version, and fills the missing minor and patch numbers with zeros
+pub(crate) fn parse_semver(version: &str) -> Result<Version> {
+    let core_len = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, rest) = version.split_at(core_len);
+    let padded = match core.matches('.').count() {
+        0 => format!("{}.0.0{}", core, rest),
+        1 => format!("{}.0{}", core, rest),
+        _ => version.to_string(),
+    };
+    Version::parse(&padded).map_err(|e| format!("Invalid version `{}`: {}", version, e))
+}
+
+/// Returns the version of the crate that's being compiled
+pub(crate) fn pkg_version() -> Result<Version> {
+    let version = std::env::var("CARGO_PKG_VERSION")
+        .map_err(|_| "Can't find the crate version, are you using cargo?".to_string())?;
+    parse_semver(&version)
+}
+
+/// Returns the version of the compiler that runs the macro, it's probed by the build script
+pub(crate) fn rustc_version() -> Result<Version> {
+    let version = option_env!("TODO2_RUSTC_VERSION")
+        .ok_or_else(|| "Can't find the compiler version".to_string())?;
+    parse_rustc_version(version)
+}
+
+/// Parses the output of `rustc -V`, e.g. `rustc 1.76.0 (07dca489a 2024-02-04)` or `rustc 1.78.0-nightly (...)`
+pub(crate) fn parse_rustc_version(output: &str) -> Result<Version> {
+    let Some(version) = output.strip_prefix("rustc ") else {
+        return Err(format!("Unexpected compiler version `{}`", output));
+    };
+    let version = version.split_whitespace().next().unwrap_or_default();
+    parse_semver(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_tokens;
+    use quote::quote;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() -> Result<()> {
+        assert_eq!(
+            parse_tokens(parse_version, quote!(>= 2.0.0))?,
+            VersionCond {
+                op: Op::Ge,
+                version: v("2.0.0")
+            }
+        );
+        assert_eq!(
+            parse_tokens(parse_version, quote!(< 1.0.0-rc.1))?,
+            VersionCond {
+                op: Op::Lt,
+                version: v("1.0.0-rc.1")
+            }
+        );
+        assert_eq!(
+            parse_tokens(parse_version, quote!(== 2.0.0-0.3.7+build.5))?,
+            VersionCond {
+                op: Op::Eq,
+                version: v("2.0.0-0.3.7+build.5")
+            }
+        );
+        assert_eq!(
+            parse_tokens(parse_version, quote!(> 2))?.version,
+            v("2.0.0")
+        );
+        assert_eq!(
+            parse_tokens(parse_version, quote!(> 2.1))?.version,
+            v("2.1.0")
+        );
+        assert_eq!(
+            parse_tokens(parse_version, quote!(> 2.1-alpha))?.version,
+            v("2.1.0-alpha")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_version() {
+        assert!(parse_tokens(parse_version, quote!(2.0.0)).is_err());
+        assert!(parse_tokens(parse_version, quote!(=> 2.0.0)).is_err());
+        assert!(parse_tokens(parse_version, quote!(>=)).is_err());
+        assert!(parse_tokens(parse_version, quote!(>= 2.0.x)).is_err());
+    }
+
+    #[test]
+    fn test_version_cond_is_met() -> Result<()> {
+        let ge = parse_tokens(parse_version, quote!(>= 2.0.0))?;
+        assert!(ge.is_met(&v("2.0.0")));
+        assert!(ge.is_met(&v("2.0.1")));
+        assert!(ge.is_met(&v("2.0.0+build")));
+        assert!(!ge.is_met(&v("1.9.9")));
+        // a pre-release has a lower precedence than the normal version
+        assert!(!ge.is_met(&v("2.0.0-rc.1")));
+
+        let ge_pre = parse_tokens(parse_version, quote!(>= 2.0.0-beta))?;
+        assert!(ge_pre.is_met(&v("2.0.0-rc.1")));
+        assert!(ge_pre.is_met(&v("2.0.0-beta.2")));
+        assert!(!ge_pre.is_met(&v("2.0.0-alpha.9")));
+        assert!(ge_pre.is_met(&v("2.0.0")));
+
+        assert!(parse_tokens(parse_version, quote!(< 1.0.0-rc.1))?.is_met(&v("1.0.0-beta.11")));
+        assert!(parse_tokens(parse_version, quote!(== 1.0.0))?.is_met(&v("1.0.0+build.7")));
+        assert!(parse_tokens(parse_version, quote!(!= 1.0.0))?.is_met(&v("1.0.0-alpha")));
+        assert!(parse_tokens(parse_version, quote!(<= 1.0.0))?.is_met(&v("1.0.0")));
+        assert!(!parse_tokens(parse_version, quote!(> 1.0.0))?.is_met(&v("1.0.0")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rustc_version() -> Result<()> {
+        assert_eq!(
+            parse_rustc_version("rustc 1.76.0 (07dca489a 2024-02-04)")?,
+            v("1.76.0")
+        );
+        assert_eq!(
+            parse_rustc_version("rustc 1.78.0-nightly (2bf78d12d 2024-02-18)")?,
+            v("1.78.0-nightly")
+        );
+        assert_eq!(parse_rustc_version("rustc 1.80.0")?, v("1.80.0"));
+        assert!(parse_rustc_version("cargo 1.76.0").is_err());
+        assert!(parse_rustc_version("").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rustc_cond_with_stubbed_version() -> Result<()> {
+        let cond = parse_tokens(parse_version, quote!(>= 1.80))?;
+        assert!(!cond.is_met(&parse_rustc_version("rustc 1.79.0 (129f3b996 2024-06-10)")?));
+        assert!(cond.is_met(&parse_rustc_version("rustc 1.80.0 (051478957 2024-07-21)")?));
+        assert!(cond.is_met(&parse_rustc_version(
+            "rustc 1.81.0-beta.1 (a1b2c3d4e 2024-07-26)"
+        )?));
+        // the nightly of a version comes before its stable release
+        assert!(!cond.is_met(&parse_rustc_version(
+            "rustc 1.80.0-nightly (bcf94dec5 2024-06-03)"
+        )?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rustc_version_is_probed() {
+        assert!(rustc_version().is_ok());
+    }
+}
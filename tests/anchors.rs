@@ -0,0 +1,45 @@
+//! Builds a crate with a relative deadline in debug then in release, they must start from the same anchor
+use std::path::Path;
+use std::process::Command;
+
+fn build(target_dir: &Path, now: &str, release: bool) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .args(["build", "--quiet", "--manifest-path"])
+        .arg(dir.join("tests/anchors/Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir)
+        .env("TODO2_BUILD_NOW", now)
+        .env_remove("TODO2_ANCHORS");
+    // The `Cargo.lock` isn't kept, so the dependencies are resolved again unless cargo is offline
+    if std::env::var_os("CARGO_NET_OFFLINE").is_some() {
+        cargo.arg("--offline");
+    }
+    if release {
+        cargo.arg("--release");
+    }
+    let output = cargo.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_profiles_share_the_anchor() {
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/anchors");
+    let _ = std::fs::remove_dir_all(&target_dir);
+
+    build(&target_dir, "1767225600", false);
+    build(&target_dir, "1767312000", true);
+    let anchors = std::fs::read_to_string(target_dir.join("todo2-anchors")).unwrap();
+    let anchors = anchors
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    // the file of the call site is only known with rustc 1.88 or newer
+    assert_eq!(anchors.len(), 1, "{:?}", anchors);
+    assert!(anchors[0].starts_with("1767225600\ttodo2-anchors\t"));
+    assert!(anchors[0].ends_with("\tRemove the hack\t"));
+}
@@ -48,6 +48,55 @@ fn test_by_condition_with_expired_year_and_month_and_minute() {
     todo!("I was not here yet!", by: 2003-2 at 9:01);
 }
 
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_date_and_12_hour_time() {
+    todo!("Delete this test", by: 2023-9-20 at 5:30:15pm);
+}
+
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_date_at_noon() {
+    todo!("Delete this test", by: 2023-9-20 at noon);
+}
+
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_date_string() {
+    todo!("Delete this test", by: "2023-09-20T09:00:00+02:00");
+}
+
+#[test]
+fn test_by_condition_with_future_date_string() {
+    todo!("Update this test", by: "2034-03-26T09:00:00Z", if: 1 == 2);
+    todo!("Update this test", by: "2034-03-26");
+}
+
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_quarter() {
+    todo!("Delete this test", by: 2023-Q3);
+}
+
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_iso_week() {
+    todo!("Delete this test", by: 2023-W52-7);
+}
+
+#[test]
+#[should_panic]
+fn test_by_condition_with_expired_month_name() {
+    todo!("Delete this test", by: 15 Mar 2023);
+}
+
+#[test]
+fn test_by_condition_with_future_planning_dates() {
+    todo!("Plan the next year", by: 2034-Q1);
+    todo!("Finish the sprint", by: 2034-W12-5 at eod);
+    todo!("Renew the certificate", by: March 2034);
+}
+
 #[test]
 fn test_by_condition_with_future_date() {
     todo!("hey, old man 🧓 update this test", by: 2033-03-26);
@@ -141,7 +190,200 @@ fn test_todo_with_time_at_symbol_future_date() {
     todo!("Do you still remember me?", by: 2033-03-26@9:00);
 }
 
+#[test]
+fn test_by_condition_with_future_date_and_time_keyword() {
+    todo!("Ship it before the weekend", by: 2034-03-24 at eod);
+}
+
 #[test]
 fn test_todo_with_time_at_symbol_and_hour() {
     todo!("I'm not sure if you are still alive", by: 2053-03-26@9);
 }
+
+#[test]
+#[should_panic]
+fn test_if_condition_with_static_cfg_true() {
+    todo!("Delete this test", if: cfg!(test) && !cfg!(any()));
+}
+
+#[test]
+fn test_if_condition_with_static_cfg_false() {
+    todo!("Are you testing in release?", if: !cfg!(test));
+}
+
+#[test]
+#[should_panic]
+fn test_if_condition_with_static_env() {
+    todo!("Delete this test", if: env!("CARGO_PKG_NAME") == "todo2");
+}
+
+#[test]
+fn test_if_condition_with_static_option_env() {
+    todo!("Who set this?", if: option_env!("TODO2_SURELY_NOT_SET").is_some());
+}
+
+const SCHEMA_VERSION: u32 = 4;
+
+#[test]
+#[should_panic]
+fn test_const_if_condition_true() {
+    todo!("Migrate the old rows", const_if: SCHEMA_VERSION >= 4);
+}
+
+#[test]
+fn test_const_if_condition_false() {
+    todo!("Drop the v5 shim", const_if: SCHEMA_VERSION >= 5);
+}
+
+#[test]
+#[should_panic]
+fn test_version_condition_met() {
+    todo!("Remove this shim", version: >= 0.1.0);
+}
+
+#[test]
+fn test_version_condition_not_met() {
+    todo!("Remove this shim before 2.0", version: >= 2.0.0);
+}
+
+#[test]
+fn test_version_condition_with_pre_release() {
+    todo!("Remove this shim before 2.0", version: >= 2.0.0-alpha.1, if: 1 == 2);
+}
+
+#[test]
+#[should_panic]
+fn test_rustc_condition_met() {
+    todo!("Use `let else`", rustc: >= 1.65);
+}
+
+#[test]
+fn test_rustc_condition_not_met() {
+    todo!("Use the new trait solver", rustc: >= 99.0);
+}
+
+#[test]
+#[should_panic]
+fn test_dep_condition_met() {
+    todo!("Drop the workaround for the old `semver`", dep: semver >= 1.0);
+}
+
+#[test]
+fn test_dep_condition_not_met() {
+    todo!("Drop the workaround", dep: semver >= 99.0);
+    todo!("Remove the `semver` shim", dep: !semver);
+}
+
+#[test]
+#[should_panic]
+fn test_dep_removed_condition() {
+    todo!("Clean up after `todo2-surely-not-a-dep`", dep: !todo2-surely-not-a-dep);
+}
+
+#[test]
+#[should_panic]
+fn test_env_condition_met() {
+    todo!("Publish it", env: CARGO_PKG_NAME == "todo2");
+}
+
+#[test]
+fn test_env_condition_not_met() {
+    todo!("Drop the debug endpoint", env: TODO2_SURELY_NOT_SET == "stable");
+    todo!("Remove the workaround", env: TODO2_SURELY_NOT_SET);
+    todo!("Rename it", env: CARGO_PKG_NAME != "todo2");
+}
+
+#[test]
+#[should_panic]
+fn test_env_unset_condition() {
+    todo!("Set it", env: !TODO2_SURELY_NOT_SET);
+}
+
+#[test]
+#[should_panic]
+fn test_in_condition_expired() {
+    todo!("Remove the hack right away", in: 0 days);
+}
+
+#[test]
+fn test_in_condition_not_expired() {
+    // The anchors outlive the test build, so the deadlines must stay far away however old they are
+    todo!("Remove the hack", in: 1200 months);
+    todo!("Remove the cache hack", in: 5200w, id: "cache");
+    todo!("Remove the cache hack", in: 5200w, id: "other-cache");
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_warn_before_doesnt_panic() {
+    todo!("Remove the hack", by: 2099-01-01, warn_before: 99999 weeks);
+    todo!("Remove the other hack", warn_before: 2w, by: 2099-01-01);
+}
+
+#[test]
+fn test_ignore_action() {
+    todo!("Ignored", by: 2020-01-01, action: ignore);
+    todo!("Ignored", action: ignore, if: true);
+    todo!("Ignored", version: >= 0.1, action: ignore);
+}
+
+#[test]
+#[should_panic]
+fn test_panic_action() {
+    todo!("Panics", by: 2020-01-01, action: panic);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_warn_action() {
+    todo!("Warns", by: 2020-01-01, action: warn);
+    todo!("Warns", if: cfg!(test), action: warn);
+    todo!("Warns", env: CARGO_PKG_NAME == "todo2", action: warn);
+}
+
+#[test]
+fn test_compile_error_action_not_met() {
+    todo!("Fails the build later", by: 2099-01-01, action: compile_error);
+    todo!("Fails the build in release", if: !cfg!(debug_assertions), action: compile_error);
+    todo!("Fails the build on 99", version: >= 99, action: compile_error);
+}
+
+#[test]
+#[should_panic]
+fn test_compile_error_action_falls_back_to_panic() {
+    let x = 1;
+    todo!("Panics", if: x == 1, action: compile_error);
+}
+
+#[test]
+fn test_metadata() {
+    todo!("Drop the v1 API", by: 2099-01-01, owner: "alice", ticket: "API-123");
+    todo!("Drop the v1 API", owner: "alice"; if: false);
+}
+
+#[test]
+#[should_panic]
+fn test_metadata_with_met_condition() {
+    todo!("Drop the v1 API", ticket: "API-123", by: 2020-01-01);
+}
+
+#[test]
+fn test_in_expression_position() {
+    let pick = |x: u32| match x {
+        0 => todo!("zero", by: 2099-01-01),
+        1 => todo!("one", version: >= 99, env: TODO2_SURELY_NOT_SET),
+        _ => {}
+    };
+    pick(0);
+    pick(1);
+}
+
+#[test]
+#[should_panic]
+fn test_in_expression_position_met() {
+    let x = 0;
+    match x {
+        0 => todo!("zero", by: 2020-01-01),
+        _ => todo!("other", if: false),
+    }
+}
@@ -0,0 +1,42 @@
+//! Builds a `#![no_std]` crate with the `no-std` feature, so the generated code can't use `std`
+use std::path::Path;
+use std::process::Command;
+
+fn build_no_std(clock: Option<&str>, features: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .args(["build", "--quiet", "--manifest-path"])
+        .arg(dir.join("tests/no_std/Cargo.toml"))
+        .args(["--features", features])
+        .env("CARGO_TARGET_DIR", dir.join("target/no_std"))
+        .env_remove("TODO2_CLOCK");
+    // The `Cargo.lock` isn't kept, so the dependencies are resolved again unless cargo is offline
+    if std::env::var_os("CARGO_NET_OFFLINE").is_some() {
+        cargo.arg("--offline");
+    }
+    if let Some(clock) = clock {
+        cargo.env("TODO2_CLOCK", clock);
+    }
+    let output = cargo.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_no_std_without_clock() {
+    build_no_std(None, "");
+}
+
+#[test]
+fn test_no_std_with_clock() {
+    build_no_std(Some("crate::unix_time"), "");
+}
+
+#[test]
+fn test_no_std_without_deadlines_ignores_the_clock() {
+    build_no_std(Some("not a path"), "undated");
+}
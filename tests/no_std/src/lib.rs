@@ -0,0 +1,29 @@
+#![no_std]
+use todo2::todo;
+
+/// The clock in `TODO2_CLOCK`
+pub fn unix_time() -> u64 {
+    1_767_225_600
+}
+
+#[cfg(not(feature = "undated"))]
+pub fn panic_sink(x: u32) {
+    todo!("Passed deadline", by: 2020-01-01, action: panic);
+    todo!("Future deadline", by: 2099-01-01 at 9:00, action: panic);
+    todo!("Runtime condition", if: x == 1, action: panic);
+    todo!("Version", version: >= 0.0, action: panic);
+}
+
+#[cfg(not(feature = "undated"))]
+pub fn log_sink(x: u32) {
+    todo!("Passed deadline", by: 2020-01-01, action: log);
+    todo!("Relative deadline", in: 0 days, action: log);
+    todo!("Runtime condition", if: x == 1, action: log);
+    todo!("Env", env: CARGO_PKG_NAME == "todo2-no-std", action: log);
+}
+
+/// There's no deadline, so it doesn't read `TODO2_CLOCK`
+pub fn undated(x: u32) {
+    todo!("Runtime condition", if: x == 2, action: panic);
+    todo!("Env", env: CARGO_PKG_NAME == "todo2-no-std", action: log);
+}
@@ -0,0 +1,296 @@
+//! Every condition kind goes through the same sinks, so each met condition is checked with each runtime sink
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::panic::catch_unwind;
+use std::sync::{Arc, Mutex, Once};
+use todo2::todo;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+const SCHEMA_VERSION: u32 = 4;
+
+/// The messages and the key-values of the log records
+static LOGS: Mutex<Vec<(String, BTreeMap<String, String>)>> = Mutex::new(Vec::new());
+
+struct CaptureLog;
+
+struct KeyValues(BTreeMap<String, String>);
+
+impl<'kvs> log::kv::VisitSource<'kvs> for KeyValues {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.0.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+impl log::Log for CaptureLog {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let mut kvs = KeyValues(BTreeMap::new());
+        record.key_values().visit(&mut kvs).unwrap();
+        LOGS.lock()
+            .unwrap()
+            .push((record.args().to_string(), kvs.0));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Returns the key-values of the record that `f` logs with the message, the tests run in parallel so each uses its own message
+fn logged(msg: &str, f: impl FnOnce()) -> Option<BTreeMap<String, String>> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CaptureLog).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+    f();
+    let logs = LOGS.lock().unwrap();
+    logs.iter()
+        .find(|(log, _)| log.contains(msg))
+        .map(|(_, kvs)| kvs.clone())
+}
+
+/// A `tracing` event with its fields formatted with `Debug`
+#[derive(Debug, Clone)]
+struct Captured {
+    level: Level,
+    fields: BTreeMap<String, String>,
+    /// The span that the event was emitted in
+    span: Option<u64>,
+}
+
+#[derive(Default)]
+struct CaptureEvents {
+    events: Mutex<Vec<Captured>>,
+    current: Mutex<Option<u64>>,
+}
+
+impl Subscriber for CaptureEvents {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event) {
+        let mut fields = BTreeMap::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        self.events.lock().unwrap().push(Captured {
+            level: *event.metadata().level(),
+            fields,
+            span: *self.current.lock().unwrap(),
+        });
+    }
+
+    fn enter(&self, span: &Id) {
+        *self.current.lock().unwrap() = Some(span.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        *self.current.lock().unwrap() = None;
+    }
+}
+
+struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+/// Returns the `tracing` events that `f` emits
+fn traced(f: impl FnOnce()) -> Vec<Captured> {
+    let subscriber = Arc::new(CaptureEvents::default());
+    tracing::subscriber::with_default(subscriber.clone(), f);
+    let events = subscriber.events.lock().unwrap().clone();
+    events
+}
+
+/// Checks a met condition with every runtime sink
+macro_rules! met_with_every_sink {
+    ($name:ident, $msg:tt, $($cond:tt)*) => {
+        #[test]
+        fn $name() {
+            assert!(catch_unwind(|| { todo!($msg, $($cond)*, action: panic); }).is_err());
+
+            assert!(logged($msg, || { todo!($msg, $($cond)*, action: log); }).is_some());
+
+            let events = traced(|| { todo!($msg, $($cond)*, action: tracing); });
+            assert_eq!(events.len(), 1);
+            assert!(events[0].fields["message"].contains($msg), "{:?}", events);
+
+            #[cfg(not(feature = "no-std"))]
+            todo!($msg, $($cond)*, action: eprint);
+        }
+    };
+}
+
+met_with_every_sink!(test_by_sinks, "Passed deadline sink", by: 2020-01-01);
+met_with_every_sink!(test_in_sinks, "Relative deadline sink", in: 0 days);
+met_with_every_sink!(test_if_sinks, "Runtime if sink", if: SCHEMA_VERSION == 4);
+met_with_every_sink!(test_static_if_sinks, "Static if sink", if: cfg!(test));
+met_with_every_sink!(test_const_if_sinks, "Const if sink", const_if: SCHEMA_VERSION >= 4);
+met_with_every_sink!(test_version_sinks, "Version sink", version: >= 0.1);
+met_with_every_sink!(test_rustc_sinks, "Rustc sink", rustc: >= 1.65);
+met_with_every_sink!(test_dep_sinks, "Dep sink", dep: semver >= 1.0);
+met_with_every_sink!(test_env_sinks, "Env sink", env: CARGO_PKG_NAME == "todo2");
+
+#[test]
+fn test_sinks_not_met() {
+    todo!("Not met panic sink", by: 2099-01-01, action: panic);
+    todo!("Not met panic sink", if: SCHEMA_VERSION == 5, action: panic);
+    todo!("Not met panic sink", if: cfg!(not(test)), action: panic);
+
+    assert!(logged("Not met log sink", || {
+        todo!("Not met log sink", by: 2099-01-01, action: log);
+        todo!("Not met log sink", const_if: SCHEMA_VERSION >= 5, action: log);
+        todo!("Not met log sink", version: >= 99, action: log);
+    })
+    .is_none());
+
+    let events = traced(|| {
+        todo!("Not met tracing sink", in: 300 days, action: tracing);
+        todo!("Not met tracing sink", dep: !semver, action: tracing);
+        todo!("Not met tracing sink", env: CARGO_PKG_NAME == "other", action: tracing);
+    });
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn test_tracing_fields() {
+    let mut line = 0;
+    let events = traced(|| {
+        let span = tracing::info_span!("request");
+        let _guard = span.enter();
+        line = line!() + 1;
+        todo!("Tracing fields", by: 2020-01-01 at 9:30, action: tracing);
+    });
+    assert_eq!(events.len(), 1, "{:?}", events);
+    let event = &events[0];
+    assert_eq!(event.level, Level::ERROR);
+    assert_eq!(event.span, Some(1));
+    assert_eq!(
+        event.fields["todo.message"],
+        "TODO: The deadline for `Tracing fields` has passed, do it now!"
+    );
+    assert_eq!(event.fields["todo.deadline"], "2020-01-01T09:30:00Z");
+    assert_eq!(event.fields["todo.kind"], "by");
+    assert_eq!(event.fields["todo.file"], file!());
+    assert_eq!(event.fields["todo.line"], line.to_string());
+
+    let events = traced(|| {
+        todo!("Tracing if fields", if: SCHEMA_VERSION == 4, action: tracing);
+    });
+    assert_eq!(events[0].fields["todo.kind"], "if");
+    assert!(!events[0].fields.contains_key("todo.deadline"));
+}
+
+// The warning is emitted at runtime, so it needs the clock of `std`
+#[cfg(not(feature = "no-std"))]
+#[test]
+#[allow(deprecated)]
+fn test_tracing_warning_before_the_deadline() {
+    let events = traced(|| {
+        todo!("Tracing warning", in: 30 days, warn_before: 60 days, action: tracing);
+    });
+    assert_eq!(events.len(), 1, "{:?}", events);
+    assert_eq!(events[0].level, Level::WARN);
+    assert!(
+        events[0].fields["message"].contains("is close"),
+        "{:?}",
+        events
+    );
+}
+
+#[test]
+fn test_tracing_metadata() {
+    let events = traced(|| {
+        todo!("Tracing metadata", if: SCHEMA_VERSION == 4, owner: "alice", ticket: "OPS-42", action: tracing);
+    });
+    assert_eq!(events[0].fields["todo.owner"], "alice");
+    assert_eq!(events[0].fields["todo.ticket"], "OPS-42");
+}
+
+#[cfg(feature = "log-kv")]
+#[test]
+fn test_log_key_values() {
+    let mut line = 0;
+    let kvs = logged("Log key values", || {
+        line = line!() + 1;
+        todo!("Log key values", by: 2020-01-01, owner: "alice", ticket: "OPS-42", action: log);
+    })
+    .unwrap();
+    assert_eq!(kvs["kind"], "by");
+    assert_eq!(kvs["deadline"], "2020-01-01T00:00:00Z");
+    let overdue = kvs["overdue_secs"].parse::<u64>().unwrap();
+    // since 2020-01-01 and at least until 2020-01-01 + 6 years
+    assert!(overdue > 6 * 365 * 86_400, "{}", overdue);
+    assert_eq!(kvs["file"], file!());
+    assert_eq!(kvs["line"], line.to_string());
+    assert_eq!(kvs["owner"], "alice");
+    assert_eq!(kvs["ticket"], "OPS-42");
+}
+
+/// Set in the child process of `test_eprint_once_per_call_site`
+#[cfg(not(feature = "no-std"))]
+const EPRINT_CHILD: &str = "TODO2_TEST_EPRINT_CHILD";
+
+#[cfg(not(feature = "no-std"))]
+#[test]
+fn test_eprint_once_per_call_site() {
+    let line = line!() + 3;
+    if std::env::var_os(EPRINT_CHILD).is_some() {
+        for _ in 0..3 {
+            todo!("Hack NASA", by: 2020-01-01, action: eprint);
+            todo!("Hack NASA again", if: SCHEMA_VERSION == 4, action: eprint);
+        }
+        return;
+    }
+    // The test harness captures the standard error, so the test runs itself in a child process
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["test_eprint_once_per_call_site", "--exact", "--nocapture"])
+        .env(EPRINT_CHILD, "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines = stderr
+        .lines()
+        .filter(|line| line.starts_with("todo2:"))
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{}", stderr);
+    assert!(
+        lines[0].starts_with("todo2: overdue since 2020-01-01 ("),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[0].ends_with(&format!(" days): Hack NASA at {}:{}", file!(), line)),
+        "{}",
+        lines[0]
+    );
+    assert_eq!(
+        lines[1],
+        format!("todo2: Hack NASA again at {}:{}", file!(), line + 1)
+    );
+}
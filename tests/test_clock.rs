@@ -0,0 +1,35 @@
+//! Run with `cargo test --features test-clock`
+#![cfg(feature = "test-clock")]
+use std::panic::catch_unwind;
+use todo2::todo;
+
+fn deadline_2099() {
+    todo!("Fast forward past me", by: 2099-01-01, action: panic);
+}
+
+#[test]
+fn test_now_override() {
+    // It's the only test in this file, so nothing else reads the variable meanwhile
+    std::env::set_var("TODO2_NOW", "2099-01-01T00:00:00Z");
+    assert!(catch_unwind(deadline_2099).is_err());
+    todo!("Not yet", by: 2099-01-01 at 00:01);
+    todo!("Not yet", in: 1200 months);
+    #[cfg(feature = "with-chrono")]
+    {
+        use chrono::TimeZone;
+        let deadline = chrono::Utc.with_ymd_and_hms(2098, 12, 31, 0, 0, 0);
+        assert!(catch_unwind(|| {
+            todo!("Fast forward past me", by: deadline, action: panic);
+        })
+        .is_err());
+    }
+
+    // It's read only once per process, by any call site
+    std::env::set_var("TODO2_NOW", "1767225600");
+    assert!(catch_unwind(deadline_2099).is_err());
+    std::env::remove_var("TODO2_NOW");
+    assert!(catch_unwind(|| {
+        todo!("Fast forward past me too", by: 2098-12-31, action: panic);
+    })
+    .is_err());
+}
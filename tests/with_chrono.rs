@@ -0,0 +1,39 @@
+#![cfg(feature = "with-chrono")]
+
+use chrono::TimeZone;
+use todo2::todo;
+
+#[test]
+#[should_panic]
+fn test_by_chrono_expr_with_expired_date() {
+    todo!("Delete this test", by: chrono::Utc.with_ymd_and_hms(2024, 2, 2, 9, 0, 0));
+}
+
+#[test]
+#[should_panic]
+fn test_by_chrono_expr_with_expired_date_time() {
+    let deadline = chrono::Utc.with_ymd_and_hms(2024, 2, 2, 9, 0, 0).unwrap();
+    todo!("Delete this test", by: deadline);
+}
+
+#[test]
+#[should_panic]
+fn test_by_chrono_expr_with_expired_fixed_offset() {
+    todo!("Delete this test", by: chrono::FixedOffset::east_opt(2 * 3600).unwrap().with_ymd_and_hms(2024, 2, 2, 9, 0, 0));
+}
+
+#[test]
+fn test_by_chrono_expr_with_future_date() {
+    todo!("Are you still here?", by: chrono::Utc.with_ymd_and_hms(2053, 3, 26, 9, 0, 0));
+}
+
+#[test]
+#[should_panic]
+fn test_by_chrono_expr_and_if_condition() {
+    todo!("Delete this test", by: chrono::Utc.with_ymd_and_hms(2053, 3, 26, 9, 0, 0), if: 1 == 1);
+}
+
+#[test]
+fn test_by_chrono_expr_next_to_raw_date() {
+    todo!("Are you still here?", by: chrono::Utc.with_ymd_and_hms(2053, 3, 26, 9, 0, 0), by: 2053-03-26);
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "with-time")]
+
+use todo2::todo;
+
+#[test]
+#[should_panic]
+fn test_by_datetime_macro_with_expired_date() {
+    todo!("Delete this test", by: time::macros::datetime!(2024-02-02 09:00 UTC));
+}
+
+#[test]
+#[should_panic]
+fn test_by_datetime_macro_with_seconds_and_expired_date() {
+    todo!("Delete this test", by: time::macros::datetime!(2024-02-02 09:00:30 +2));
+}
+
+#[test]
+fn test_by_datetime_macro_with_future_date() {
+    todo!("Are you still here?", by: time::macros::datetime!(2053-03-26 09:00 UTC));
+}
+
+#[test]
+#[should_panic]
+fn test_by_offset_date_time_with_expired_date() {
+    let deadline = time::macros::datetime!(2024-02-02 09:00 UTC);
+    todo!("Delete this test", by: deadline);
+}
+
+#[test]
+#[should_panic]
+fn test_by_primitive_date_time_with_expired_date() {
+    todo!("Delete this test", by: time::macros::datetime!(2024-02-02 09:00:30));
+}
+
+#[test]
+fn test_by_offset_date_time_with_future_date() {
+    todo!("Are you still here?", by: time::OffsetDateTime::now_utc() + time::Duration::days(1));
+}
+
+#[test]
+#[should_panic]
+fn test_by_datetime_macro_and_if_condition() {
+    todo!("Delete this test", by: time::macros::datetime!(2053-03-26 09:00 UTC), if: 1 == 1);
+}